@@ -43,6 +43,12 @@ class PeerTypes(Enum):
     NoneFound = ""
 
 
+class SessionTypes(Enum):
+    PublicIxp = "IXP"
+    PrivateInterconnect = "PNI"
+    RouteServer = "RS"
+
+
 class PeerLocations(Enum):
     Africa = "Africa"
     AsiaPac = "Asia Pac"
@@ -53,6 +59,37 @@ class PeerLocations(Enum):
     NoneFound = "None Found"
 
 
+def get_locations(data: dict[str, Any]) -> list[str]:
+    """
+    Return every location found in the peering data, sorted by continent.
+    Locations are "Continent[/Region[/Country[/Metro]]]" paths, depending on the
+    --location-level the data was written with.
+    """
+    locations = set(
+        location
+        for peers in data.values()
+        for location_peerings in peers["peers"].values()
+        for location in location_peerings["location_peerings"].keys()
+    )
+    # Always show a column for each continent
+    locations.update(location.name for location in PeerLocations)
+    continents = [location.name for location in PeerLocations]
+    return sorted(
+        locations,
+        key=lambda location: (
+            continents.index(location.split("/")[0]),
+            location,
+        ),
+    )
+
+
+def get_location_heading(location: str) -> str:
+    continent, *levels = location.split("/")
+    return " / ".join(
+        [PeerLocations[continent].value] + [level or "?" for level in levels]
+    )
+
+
 cli_args: argparse.Namespace
 
 
@@ -105,7 +142,15 @@ def mirror_data(peering_data: dict[str, Any]) -> dict[str, Any]:
                         peer_type = PeerTypes.Customer.name
 
                     if peer_type not in peerings_ptr:
-                        peerings_ptr[peer_type] = {"peerings_by_ver": {}}
+                        peerings_ptr[peer_type] = {
+                            "peerings_by_ver": {},
+                            "session_types": [],
+                        }
+
+                    session_types = peerings_ptr[peer_type]["session_types"]
+                    for session_type in peerings_by_ver.get("session_types", []):
+                        if session_type not in session_types:
+                            session_types.append(session_type)
 
                     version_prt = peerings_ptr[peer_type]["peerings_by_ver"]
 
@@ -138,13 +183,15 @@ def generate_plot_data(data: dict[str, Any]) -> dict[str, Any]:
         "non_peer": "lightgoldenrodyellow",
     }
 
+    locations = get_locations(data)
+
     fill_colours: dict[int, list[list[str]]] = {}
     for t1_asn in T1_ASNS.keys():
         fill_colours[t1_asn] = [
             [fill_colour_map["no_data"] for _ in T1_ASNS.keys()]
         ] + [
             [fill_colour_map["no_data"] for _ in T1_ASNS.keys()]
-            for _ in PeerLocations
+            for _ in locations
         ]
 
     text_colours: dict[int, list[list[str]]] = {}
@@ -153,12 +200,12 @@ def generate_plot_data(data: dict[str, Any]) -> dict[str, Any]:
             [text_colour_map["no_data"] for _ in T1_ASNS.keys()]
         ] + [
             [text_colour_map["no_data"] for _ in T1_ASNS.keys()]
-            for _ in PeerLocations
+            for _ in locations
         ]
 
     # Values are by column
     values: dict[int, list[list[str]]] = {
-        t1_asn: [[]] + [[] for _ in PeerLocations] for t1_asn in T1_ASNS.keys()
+        t1_asn: [[]] + [[] for _ in locations] for t1_asn in T1_ASNS.keys()
     }
 
     for t1_asn in T1_ASNS.keys():
@@ -185,24 +232,30 @@ def generate_plot_data(data: dict[str, Any]) -> dict[str, Any]:
                     values[t1_asn][i].append("")
                 continue
 
-            locations: dict[str, dict[str, dict[str, Any]]] = data[
+            location_peerings: dict[str, dict[str, dict[str, Any]]] = data[
                 str(t1_asn)
             ]["peers"][str(local_asn)]["location_peerings"]
-            for loc_index, location in enumerate(PeerLocations):
-                if not location.name in locations:
+            for loc_index, location in enumerate(locations):
+                if not location in location_peerings:
                     values[t1_asn][loc_index + 1].append("")
                     continue
 
-                peer_types = set(
-                    sorted(
-                        list(
-                            locations[location.name]["peerings_in_loc"].keys()
+                peerings_in_loc = location_peerings[location]["peerings_in_loc"]
+                peer_types = set(peerings_in_loc.keys())
+                # e.g. "Peer (IXP, PNI)" for a peering seen over both
+                labels = []
+                for peer_type in sorted(peer_types):
+                    session_types = [
+                        SessionTypes[session_type].value
+                        for session_type in sorted(
+                            peerings_in_loc[peer_type].get("session_types", [])
                         )
+                    ]
+                    sessions = (
+                        f" ({', '.join(session_types)})" if session_types else ""
                     )
-                )
-                values[t1_asn][loc_index + 1].append(
-                    f"{', '.join(peer_types)}"
-                )
+                    labels.append(f"{peer_type}{sessions}")
+                values[t1_asn][loc_index + 1].append(", ".join(labels))
 
                 if peer_types == set([PeerTypes.Peer.name]):
                     mapping = "peer"
@@ -227,7 +280,8 @@ def generate_plot_data(data: dict[str, Any]) -> dict[str, Any]:
 
 def plot_peerings(data: dict[str, Any]) -> None:
     col_headings = ["<b>ASN</b>"] + [
-        f"<b>{location.value}</b>" for location in PeerLocations
+        f"<b>{get_location_heading(location)}</b>"
+        for location in get_locations(data)
     ]
 
     data = generate_plot_data(data)
@@ -1,5 +1,79 @@
 pub mod cli_args {
-    use clap::{Args, Parser, Subcommand};
+    use clap::{Args, Parser, Subcommand, ValueEnum};
+
+    /// Where MRT RIB files are downloaded from
+    #[derive(Clone, Copy, Debug, ValueEnum)]
+    pub enum MrtSource {
+        /// RIS and RouteViews, as indexed by the BGPKIT broker
+        Broker,
+        /// bgproutes.io vantage points
+        Bgproutes,
+        /// Packet Clearing House route collectors
+        Pch,
+        /// Any HTTP directory listing, set with --source-url
+        HttpDir,
+        /// A local JSON or CSV manifest of RIB files, set with --source-url
+        Manifest,
+        /// A local directory tree of RIB files, set with --source-url
+        LocalDir,
+    }
+
+    /// The project which operates a route collector
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+    pub enum Project {
+        Ris,
+        Routeviews,
+        Bgproutes,
+        Pch,
+    }
+
+    impl Project {
+        /// The source name which collectors of this project are reported under
+        pub fn get_source_name(&self) -> &'static str {
+            match self {
+                Self::Ris => "ris",
+                Self::Routeviews => "route-views",
+                Self::Bgproutes => "bgproutes.io",
+                Self::Pch => "pch",
+            }
+        }
+    }
+
+    /// How threads are divided between RIB files when parsing
+    #[derive(Clone, Copy, Debug, ValueEnum)]
+    pub enum Strategy {
+        /// Each file is parsed by a single thread
+        PerFile,
+        /// Files are parsed one at a time, each split across all threads
+        PerRecord,
+        /// Threads are divided evenly between files, e.g. 3 files on 24 threads get 8 threads each
+        Auto,
+    }
+
+    impl Strategy {
+        /// The number of threads each file is parsed with
+        pub fn get_threads_per_file(&self, threads: usize, files: usize) -> usize {
+            match self {
+                Self::PerFile => 1,
+                Self::PerRecord => threads,
+                Self::Auto => (threads / files.max(1)).max(1),
+            }
+        }
+    }
+
+    /// How precisely peer locations are reported, peerings found at a more precise
+    /// level are rolled up into this level
+    #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, ValueEnum)]
+    pub enum LocationLevel {
+        /// e.g. Europe
+        Continent,
+        /// e.g. AsiaPac/SouthAsia
+        Region,
+        /// e.g. AsiaPac/SouthAsia/IN
+        Country,
+        /// e.g. AsiaPac/SouthAsia/IN/BOM
+        Metro,
+    }
 
     /// Download RIB files by specifying an output folder and a date.
     /// The downloaded files will then be parsed (existing files are not re-downloaded).
@@ -12,29 +86,134 @@ pub mod cli_args {
         /// Download RIBs for yyyy-mm-dd
         #[arg(short = 'y', long, default_value_t = String::from("2026-01-14"))]
         pub ribs_ymd: String,
+
+        /// Comma separated list of hours (UTC) to download RIBs for.
+        /// RIS dumps RIBs every 8 hours and RouteViews every 2 hours.
+        #[arg(long, value_delimiter = ',', default_value = "0",
+              value_parser = clap::value_parser!(u32).range(0..24))]
+        pub hours: Vec<u32>,
+
+        /// Download every RIB dumped during the day, instead of specific hours
+        #[arg(long, conflicts_with = "hours")]
+        pub all_hours: bool,
+
+        /// Download RIBs from this source
+        #[arg(short = 's', long, value_enum, default_value_t = MrtSource::Broker)]
+        pub source: MrtSource,
+
+        /// Override the source base URL (required for http-dir), or the local path of a
+        /// manifest or directory tree. May contain {yyyy}, {mm}, {dd} and {ts} placeholders.
+        #[arg(long)]
+        pub source_url: Option<String>,
+
+        /// Only download files whose name matches this regex, may contain placeholders
+        #[arg(long)]
+        pub file_regex: Option<String>,
+
+        /// Parse RIBs directly from HTTP, without saving them to disk
+        #[arg(long)]
+        pub stream: bool,
+
+        /// Comma separated list of collectors or URL substrings to not download,
+        /// in addition to the source's built-in skip list
+        #[arg(long, value_delimiter = ',')]
+        pub skip: Vec<String>,
+
+        /// Comma separated list of collectors to download, e.g. rrc00,route-views2
+        #[arg(long, value_delimiter = ',')]
+        pub collectors: Vec<String>,
+
+        /// Comma separated list of collectors to not download
+        #[arg(long, value_delimiter = ',')]
+        pub exclude_collectors: Vec<String>,
+
+        /// Comma separated list of projects to download from
+        #[arg(long, value_enum, value_delimiter = ',')]
+        pub project: Vec<Project>,
+
+        /// Write the list of RIB files for this run to a JSON manifest,
+        /// which can be used later with --source manifest
+        #[arg(long)]
+        pub write_manifest: Option<String>,
+
+        /// List the files, and total size, which would be downloaded then exit
+        #[arg(long)]
+        pub dry_run: bool,
     }
 
-    /// Parse a RIB file which already exists locally - split across multiple thread.
-    #[derive(Debug, Args)]
-    pub struct FileArgs {
-        /// Path to an existing MRT file to parse
-        #[arg(short = 'f', long)]
-        pub rib_file: String,
+    impl DownloadArgs {
+        pub fn get_hours(&self) -> Vec<u32> {
+            if self.all_hours {
+                (0..24).collect()
+            } else {
+                self.hours.clone()
+            }
+        }
     }
 
-    /// Parse RIB files which already exist locally - each file by a different thread.
+    /// RIB files which already exist locally, for the file and files subcommands
     #[derive(Debug, Args)]
-    pub struct FilesArgs {
+    pub struct RibFilesArgs {
         /// Space separated list of existing MRT files to parse
-        #[arg(short = 'f', long, value_delimiter = ' ', num_args = 1..)]
+        #[arg(
+            short = 'f',
+            long,
+            alias = "rib-file",
+            required = true,
+            value_delimiter = ' ',
+            num_args = 1..
+        )]
         pub rib_files: Vec<String>,
     }
 
+    /// Check the community mappings for overlapping, contradictory and unreachable entries,
+    /// and gaps in coverage, then exit without parsing any RIB files. Exits non-zero if
+    /// errors are found.
+    #[derive(Debug, Args)]
+    pub struct LintMappingsArgs {
+        /// Also exit non-zero if warnings are found
+        #[arg(long)]
+        pub deny_warnings: bool,
+    }
+
+    /// Read the remarks of an aut-num object in a local RPSL dump, e.g. ripe.db.aut-num.gz,
+    /// and write candidate community mappings for review, then exit without parsing any
+    /// RIB files.
+    #[derive(Debug, Args)]
+    pub struct ImportRpslArgs {
+        /// RPSL dump file to read, may be gzip or bzip2 compressed
+        #[arg(short = 'f', long)]
+        pub dump_file: String,
+
+        /// ASN of the aut-num to import, e.g. 3491 or AS3491
+        #[arg(short, long, value_parser = parse_asn)]
+        pub aut_num: u32,
+
+        /// Write the candidate mappings to this file
+        #[arg(short, long, default_value_t = String::from("./results/rpsl_mappings.rs"))]
+        pub output: String,
+    }
+
+    fn parse_asn(asn: &str) -> Result<u32, String> {
+        let digits = asn
+            .strip_prefix("AS")
+            .or_else(|| asn.strip_prefix("as"))
+            .unwrap_or(asn);
+        digits
+            .parse::<u32>()
+            .map_err(|_| format!("{} isn't an ASN", asn))
+    }
+
     #[derive(Subcommand, Debug)]
     pub enum RibsSource {
-        Download(DownloadArgs),
-        File(FileArgs),
-        Files(FilesArgs),
+        Download(Box<DownloadArgs>),
+        /// Parse RIB files which already exist locally - each file split across multiple threads,
+        /// with threads divided evenly between files by default.
+        File(RibFilesArgs),
+        /// Parse RIB files which already exist locally - each file by a different thread by default.
+        Files(RibFilesArgs),
+        LintMappings(LintMappingsArgs),
+        ImportRpsl(ImportRpslArgs),
     }
 
     /// Scan MRT RIB dumps, looking for tier 1 ASNs and communities
@@ -56,9 +235,55 @@ pub mod cli_args {
         #[arg(long, default_value_t = String::from("./results/triple_t1_paths.json"))]
         pub triple_t1_paths: String,
 
+        /// Serialise per-file and per-collector parsing statistics to JSON file
+        #[arg(long, default_value_t = String::from("./results/run_stats.json"))]
+        pub run_stats: String,
+
+        /// Serialise which collectors and collector peers see each T1 ASN to JSON file
+        #[arg(long, default_value_t = String::from("./results/coverage.json"))]
+        pub coverage: String,
+
+        /// Serialise a ranking of collector peers by the new peerings they contribute to JSON file
+        #[arg(long, default_value_t = String::from("./results/vantage_points.json"))]
+        pub vantage_points: String,
+
+        /// Serialise which peerings each collector saw in each RIB snapshot to JSON file
+        #[arg(long, default_value_t = String::from("./results/snapshots.json"))]
+        pub snapshots: String,
+
+        /// How precisely peer locations are written to the peering data, vantage point and snapshot files
+        #[arg(long, value_enum, default_value_t = LocationLevel::Continent)]
+        pub location_level: LocationLevel,
+
+        /// Serialise how often per-neighbor communities match the next ASN in the AS path to JSON file
+        #[arg(long, default_value_t = String::from("./results/neighbor_checks.json"))]
+        pub neighbor_checks: String,
+
+        /// Serialise the unmapped communities of each T1 ASN, seen on routes which couldn't be classified, to JSON file
+        #[arg(long, default_value_t = String::from("./results/unmapped_communities.json"))]
+        pub unmapped_communities: String,
+
+        /// Infer the meaning of unmapped communities from the relationship of each T1 with the
+        /// next ASN in the path, and write draft mappings for review to this file
+        #[arg(long)]
+        pub draft_mappings: Option<String>,
+
+        /// CAIDA style AS relationships file, used by --draft-mappings for neighbors which
+        /// the mapped communities don't give a peer type for
+        #[arg(long, requires = "draft_mappings")]
+        pub as_relationships: Option<String>,
+
         /// Number of threads to use for parsing MRT files
         #[arg(short, long, default_value_t = 1)]
         pub threads: u32,
+
+        /// How to divide threads between MRT files [default: per-file for files, otherwise auto]
+        #[arg(long, value_enum)]
+        pub strategy: Option<Strategy>,
+
+        /// Abort on the first malformed record or failed download, instead of skipping it
+        #[arg(long)]
+        pub strict: bool,
     }
 
     impl CliArgs {
@@ -78,25 +303,39 @@ pub mod cli_args {
             }
         }
 
-        pub fn get_rib_file(&self) -> &String {
-            if let RibsSource::File(args) = &self.ribs_source {
-                &args.rib_file
+        pub fn get_download_args(&self) -> &DownloadArgs {
+            if let RibsSource::Download(args) = &self.ribs_source {
+                args
             } else {
-                panic!("No RIB file option to unpack");
+                panic!("No download options to unpack");
             }
         }
 
         pub fn get_rib_files(&self) -> &Vec<String> {
-            if let RibsSource::Files(args) = &self.ribs_source {
-                &args.rib_files
-            } else {
-                panic!("No RIB file list option to unpack");
+            match &self.ribs_source {
+                RibsSource::File(args) | RibsSource::Files(args) => &args.rib_files,
+                RibsSource::Download(_)
+                | RibsSource::LintMappings(_)
+                | RibsSource::ImportRpsl(_) => {
+                    panic!("No RIB file list option to unpack")
+                }
             }
         }
 
+        pub fn get_strategy(&self) -> Strategy {
+            self.strategy.unwrap_or(match self.ribs_source {
+                RibsSource::Files(_) => Strategy::PerFile,
+                _ => Strategy::Auto,
+            })
+        }
+
         pub fn download(&self) -> bool {
             matches!(self.ribs_source, RibsSource::Download(_))
         }
+
+        pub fn stream(&self) -> bool {
+            matches!(&self.ribs_source, RibsSource::Download(args) if args.stream)
+        }
     }
 
     pub fn parse_cli_arg() -> CliArgs {
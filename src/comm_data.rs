@@ -1,58 +1,62 @@
-use crate::comm_mappings::community_mappings::CommMappings;
+use crate::comm_mappings::community_mappings::{AsnMappings, CommMappings};
+use crate::comm_matchers::community_matchers::CommunityMatcher;
 use crate::mrt_asn::asn::MrtAsn;
 use crate::mrt_communities::standard_communities::StandardCommunity;
-use crate::peer_attrs::peer_data::{PeerLocation, PeerType};
+use crate::peer_attrs::peer_data::{Continent, PeerLocation, PeerType, SessionType};
 use std::collections::HashMap;
 
-pub fn insert_comm_mapping(asn_mappings: &mut HashMap<MrtAsn, CommMappings>) {
+/// Parse a community matcher which is known to be valid
+fn matcher(matcher: &str) -> CommunityMatcher {
+    matcher.parse().unwrap()
+}
+
+/// Parse a peer location which is known to be valid
+fn location(location: &str) -> PeerLocation {
+    location.parse().unwrap()
+}
+
+/// Insert the community mappings of each Tier 1. Mappings are valid at all times unless
+/// inserted with insert_between, so when an operator renumbers its communities, the old
+/// mappings should be given an end date and the new ones a start date, e.g.
+/// `asn_mappings.insert_between(asn, None, Some(Ymd::parse("2024-06-01").unwrap()), cm)`
+pub fn insert_comm_mapping(asn_mappings: &mut AsnMappings) {
     /* Cogent */
-    asn_mappings.insert(
-        MrtAsn::from_u32(174),
-        CommMappings::new(
-            HashMap::from([
-                (StandardCommunity::new(174, 21000), PeerType::Peer),
-                (StandardCommunity::new(174, 21001), PeerType::Customer),
-                (StandardCommunity::new(174, 21100), PeerType::Peer),
-                (StandardCommunity::new(174, 21101), PeerType::Customer),
-                (StandardCommunity::new(174, 21200), PeerType::Peer),
-                (StandardCommunity::new(174, 21201), PeerType::Customer),
-                (StandardCommunity::new(174, 21300), PeerType::Peer),
-                (StandardCommunity::new(174, 21301), PeerType::Customer),
-                (StandardCommunity::new(174, 21400), PeerType::Peer),
-                (StandardCommunity::new(174, 21401), PeerType::Customer),
-                (StandardCommunity::new(174, 21500), PeerType::Peer),
-                (StandardCommunity::new(174, 21501), PeerType::Customer),
-            ]),
-            HashMap::from([
-                (
-                    StandardCommunity::new(174, 21000),
-                    PeerLocation::NorthAmerica,
-                ),
-                (
-                    StandardCommunity::new(174, 21001),
-                    PeerLocation::NorthAmerica,
-                ),
-                (StandardCommunity::new(174, 21100), PeerLocation::Europe),
-                (StandardCommunity::new(174, 21101), PeerLocation::Europe),
-                (StandardCommunity::new(174, 21200), PeerLocation::AsiaPac),
-                (StandardCommunity::new(174, 21201), PeerLocation::AsiaPac),
-                (
-                    StandardCommunity::new(174, 21300),
-                    PeerLocation::SouthAmerica,
-                ),
-                (
-                    StandardCommunity::new(174, 21301),
-                    PeerLocation::SouthAmerica,
-                ),
-                (StandardCommunity::new(174, 21400), PeerLocation::AsiaPac),
-                (StandardCommunity::new(174, 21401), PeerLocation::AsiaPac),
-                (StandardCommunity::new(174, 21500), PeerLocation::Africa),
-                (StandardCommunity::new(174, 21501), PeerLocation::Africa),
-            ]),
-        ),
+    let mut cm = CommMappings::new(
+        HashMap::from([
+            (StandardCommunity::new(174, 21000), PeerType::Peer),
+            (StandardCommunity::new(174, 21001), PeerType::Customer),
+            (StandardCommunity::new(174, 21100), PeerType::Peer),
+            (StandardCommunity::new(174, 21101), PeerType::Customer),
+            (StandardCommunity::new(174, 21200), PeerType::Peer),
+            (StandardCommunity::new(174, 21201), PeerType::Customer),
+            (StandardCommunity::new(174, 21300), PeerType::Peer),
+            (StandardCommunity::new(174, 21301), PeerType::Customer),
+            (StandardCommunity::new(174, 21400), PeerType::Peer),
+            (StandardCommunity::new(174, 21401), PeerType::Customer),
+            (StandardCommunity::new(174, 21500), PeerType::Peer),
+            (StandardCommunity::new(174, 21501), PeerType::Customer),
+        ]),
+        HashMap::from([
+            (StandardCommunity::new(174, 21000), Continent::NorthAmerica),
+            (StandardCommunity::new(174, 21001), Continent::NorthAmerica),
+            (StandardCommunity::new(174, 21100), Continent::Europe),
+            (StandardCommunity::new(174, 21101), Continent::Europe),
+            (StandardCommunity::new(174, 21200), Continent::AsiaPac),
+            (StandardCommunity::new(174, 21201), Continent::AsiaPac),
+            (StandardCommunity::new(174, 21300), Continent::SouthAmerica),
+            (StandardCommunity::new(174, 21301), Continent::SouthAmerica),
+            (StandardCommunity::new(174, 21500), Continent::Africa),
+            (StandardCommunity::new(174, 21501), Continent::Africa),
+        ]),
     );
 
+    // Australia
+    cm.add_peer_location(matcher("174:21400-21401"), location("AsiaPac/Oceania/AU"));
+    asn_mappings.insert(MrtAsn::from_u32(174), cm);
+
     /* Verizon */
+    // Verizon's communities use 0 as their ASN field, they are only attributed to
+    // Verizon if no other network on the AS path has a mapping for them
     asn_mappings.insert(
         MrtAsn::from_u32(701),
         CommMappings::new(
@@ -67,113 +71,25 @@ pub fn insert_comm_mapping(asn_mappings: &mut HashMap<MrtAsn, CommMappings>) {
     /* Vodafone */
     let mut cm = CommMappings::new(HashMap::new(), HashMap::new());
 
-    // Regional learned from customer communities
-    for i in 11000..=18999 {
-        cm.add_peer_type(StandardCommunity::new(1273, i), PeerType::Customer);
-    }
-
-    // Regional learned from peer communities
-    for i in 21000..=28999 {
-        cm.add_peer_type(StandardCommunity::new(1273, i), PeerType::Peer);
-    }
-
-    // Regional learned from upstream communities
-    for i in 31000..=38999 {
-        cm.add_peer_type(StandardCommunity::new(1273, i), PeerType::Upstream);
-    }
+    // Regional learned from customer, peer and upstream communities
+    cm.add_peer_type(matcher("1273:1[1-8]xxx"), PeerType::Customer);
+    cm.add_peer_type(matcher("1273:2[1-8]xxx"), PeerType::Peer);
+    cm.add_peer_type(matcher("1273:3[1-8]xxx"), PeerType::Upstream);
 
     // Leaned from upstream Arelion
-    for i in 39970..=39979 {
-        cm.add_peer_type(StandardCommunity::new(1273, i), PeerType::Upstream);
-    }
-
-    // Learned in North America
-    for i in 11000..=11999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::NorthAmerica);
-    }
-    for i in 21000..=21999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::NorthAmerica);
-    }
-    for i in 31000..=31999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::NorthAmerica);
-    }
-
-    // Learned in Europe
-    for i in 12000..=12999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::Europe);
-    }
-    for i in 22000..=22999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::Europe);
-    }
-    for i in 32000..=32999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::Europe);
-    }
-
-    // Learned in Asia
-    for i in 13000..=13999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::AsiaPac);
-    }
-    for i in 23000..=23999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::AsiaPac);
-    }
-    for i in 33000..=33999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::AsiaPac);
-    }
-
-    // Learned in Australia
-    for i in 14000..=14999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::AsiaPac);
-    }
-    for i in 24000..=24999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::AsiaPac);
-    }
-    for i in 34000..=34999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::AsiaPac);
-    }
+    cm.add_peer_type(matcher("1273:3997x"), PeerType::Upstream);
+    cm.add_neighbor(matcher("1273:3997x"), MrtAsn::from_u32(1299));
 
-    // Learned in South America
-    for i in 15000..=15999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::SouthAmerica);
-    }
-    for i in 25000..=25999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::SouthAmerica);
-    }
-    for i in 35000..=35999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::SouthAmerica);
-    }
-
-    // Learned in Africa
-    for i in 16000..=16999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::Africa);
-    }
-    for i in 26000..=26999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::Africa);
-    }
-    for i in 36000..=36999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::Africa);
-    }
-
-    // Learned in Middle East
-    for i in 17000..=17999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::Europe);
-    }
-    for i in 27000..=27999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::Europe);
-    }
-    for i in 37000..=37999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::Europe);
-    }
-
-    // Learned in India
-    for i in 18000..=18999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::AsiaPac);
-    }
-    for i in 28000..=28999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::AsiaPac);
-    }
-    for i in 38000..=38999 {
-        cm.add_peer_location(StandardCommunity::new(1273, i), PeerLocation::AsiaPac);
-    }
+    // The second digit of the regional communities is where the route was learned
+    cm.add_peer_location(matcher("1273:[1-3]1xxx"), Continent::NorthAmerica.into());
+    cm.add_peer_location(matcher("1273:[1-3]2xxx"), Continent::Europe.into());
+    // Asia
+    cm.add_peer_location(matcher("1273:[1-3]3xxx"), Continent::AsiaPac.into());
+    cm.add_peer_location(matcher("1273:[1-3]4xxx"), location("AsiaPac/Oceania/AU"));
+    cm.add_peer_location(matcher("1273:[1-3]5xxx"), Continent::SouthAmerica.into());
+    cm.add_peer_location(matcher("1273:[1-3]6xxx"), Continent::Africa.into());
+    cm.add_peer_location(matcher("1273:[1-3]7xxx"), Continent::MiddleEast.into());
+    cm.add_peer_location(matcher("1273:[1-3]8xxx"), location("AsiaPac/SouthAsia/IN"));
 
     asn_mappings.insert(MrtAsn::from_u32(1273), cm);
 
@@ -190,18 +106,12 @@ pub fn insert_comm_mapping(asn_mappings: &mut HashMap<MrtAsn, CommMappings>) {
                 (StandardCommunity::new(1299, 37000), PeerType::Customer),
             ]),
             HashMap::from([
-                (StandardCommunity::new(1299, 20000), PeerLocation::Europe),
-                (
-                    StandardCommunity::new(1299, 25000),
-                    PeerLocation::NorthAmerica,
-                ),
-                (StandardCommunity::new(1299, 27000), PeerLocation::AsiaPac),
-                (StandardCommunity::new(1299, 30000), PeerLocation::Europe),
-                (
-                    StandardCommunity::new(1299, 35000),
-                    PeerLocation::NorthAmerica,
-                ),
-                (StandardCommunity::new(1299, 37000), PeerLocation::AsiaPac),
+                (StandardCommunity::new(1299, 20000), Continent::Europe),
+                (StandardCommunity::new(1299, 25000), Continent::NorthAmerica),
+                (StandardCommunity::new(1299, 27000), Continent::AsiaPac),
+                (StandardCommunity::new(1299, 30000), Continent::Europe),
+                (StandardCommunity::new(1299, 35000), Continent::NorthAmerica),
+                (StandardCommunity::new(1299, 37000), Continent::AsiaPac),
             ]),
         ),
     );
@@ -215,26 +125,14 @@ pub fn insert_comm_mapping(asn_mappings: &mut HashMap<MrtAsn, CommMappings>) {
                 (StandardCommunity::new(2914, 420), PeerType::Peer),
             ]),
             HashMap::from([
-                (
-                    StandardCommunity::new(2914, 3000),
-                    PeerLocation::NorthAmerica,
-                ),
-                (
-                    StandardCommunity::new(2914, 3075),
-                    PeerLocation::NorthAmerica,
-                ),
-                (StandardCommunity::new(2914, 3200), PeerLocation::Europe),
-                (StandardCommunity::new(2914, 3275), PeerLocation::Europe),
-                (StandardCommunity::new(2914, 3400), PeerLocation::AsiaPac),
-                (StandardCommunity::new(2914, 3475), PeerLocation::AsiaPac),
-                (
-                    StandardCommunity::new(2914, 3600),
-                    PeerLocation::SouthAmerica,
-                ),
-                (
-                    StandardCommunity::new(2914, 3675),
-                    PeerLocation::SouthAmerica,
-                ),
+                (StandardCommunity::new(2914, 3000), Continent::NorthAmerica),
+                (StandardCommunity::new(2914, 3075), Continent::NorthAmerica),
+                (StandardCommunity::new(2914, 3200), Continent::Europe),
+                (StandardCommunity::new(2914, 3275), Continent::Europe),
+                (StandardCommunity::new(2914, 3400), Continent::AsiaPac),
+                (StandardCommunity::new(2914, 3475), Continent::AsiaPac),
+                (StandardCommunity::new(2914, 3600), Continent::SouthAmerica),
+                (StandardCommunity::new(2914, 3675), Continent::SouthAmerica),
             ]),
         ),
     );
@@ -243,19 +141,15 @@ pub fn insert_comm_mapping(asn_mappings: &mut HashMap<MrtAsn, CommMappings>) {
     let mut cm = CommMappings::new(
         HashMap::from([(StandardCommunity::new(3257, 4000), PeerType::Customer)]),
         HashMap::from([
-            (StandardCommunity::new(3257, 50001), PeerLocation::Europe),
-            (
-                StandardCommunity::new(3257, 50002),
-                PeerLocation::NorthAmerica,
-            ),
-            (StandardCommunity::new(3257, 50003), PeerLocation::AsiaPac),
+            (StandardCommunity::new(3257, 50001), Continent::Europe),
+            (StandardCommunity::new(3257, 50002), Continent::NorthAmerica),
+            (StandardCommunity::new(3257, 50003), Continent::AsiaPac),
         ]),
     );
 
-    // Regional learned from peer communities
-    for i in 30000..=39999 {
-        cm.add_peer_type(StandardCommunity::new(3257, i), PeerType::Peer);
-    }
+    // Regional learned from peer communities, numbered per private interconnect
+    cm.add_peer_type(matcher("3257:3xxxx"), PeerType::Peer);
+    cm.add_session_type(matcher("3257:3xxxx"), SessionType::PrivateInterconnect);
     asn_mappings.insert(MrtAsn::from_u32(3257), cm);
 
     /* DTAG */
@@ -267,12 +161,9 @@ pub fn insert_comm_mapping(asn_mappings: &mut HashMap<MrtAsn, CommMappings>) {
                 (StandardCommunity::new(3320, 9020), PeerType::Peer),
             ]),
             HashMap::from([
-                (StandardCommunity::new(3320, 2010), PeerLocation::Europe),
-                (
-                    StandardCommunity::new(3320, 2020),
-                    PeerLocation::NorthAmerica,
-                ),
-                (StandardCommunity::new(3320, 2030), PeerLocation::AsiaPac),
+                (StandardCommunity::new(3320, 2010), Continent::Europe),
+                (StandardCommunity::new(3320, 2020), Continent::NorthAmerica),
+                (StandardCommunity::new(3320, 2030), Continent::AsiaPac),
             ]),
         ),
     );
@@ -286,63 +177,43 @@ pub fn insert_comm_mapping(asn_mappings: &mut HashMap<MrtAsn, CommMappings>) {
                 (StandardCommunity::new(3356, 666), PeerType::Peer),
             ]),
             HashMap::from([
-                (StandardCommunity::new(3356, 2), PeerLocation::Europe),
-                (StandardCommunity::new(3356, 3), PeerLocation::NorthAmerica),
-                (StandardCommunity::new(3356, 4), PeerLocation::AsiaPac),
-                (StandardCommunity::new(3356, 5), PeerLocation::SouthAmerica),
+                (StandardCommunity::new(3356, 2), Continent::Europe),
+                (StandardCommunity::new(3356, 3), Continent::NorthAmerica),
+                (StandardCommunity::new(3356, 4), Continent::AsiaPac),
+                (StandardCommunity::new(3356, 5), Continent::SouthAmerica),
             ]),
         ),
     );
 
     /* PCCW */
-    asn_mappings.insert(
-        MrtAsn::from_u32(3491),
-        CommMappings::new(
-            HashMap::from([
-                (StandardCommunity::new(3491, 100), PeerType::Customer),
-                (StandardCommunity::new(3491, 200), PeerType::Customer),
-                (StandardCommunity::new(3491, 300), PeerType::Customer),
-                (StandardCommunity::new(3491, 400), PeerType::Customer),
-                (StandardCommunity::new(3491, 500), PeerType::Customer),
-                (StandardCommunity::new(3491, 700), PeerType::Customer),
-                (StandardCommunity::new(3491, 1000), PeerType::Peer),
-                (StandardCommunity::new(3491, 2000), PeerType::Peer),
-                (StandardCommunity::new(3491, 3000), PeerType::Peer),
-                (StandardCommunity::new(3491, 4000), PeerType::Peer),
-                (StandardCommunity::new(3491, 5000), PeerType::Peer),
-                (StandardCommunity::new(3491, 7000), PeerType::Peer),
-                (StandardCommunity::new(3491, 9001), PeerType::Customer),
-                (StandardCommunity::new(3491, 9002), PeerType::Peer),
-            ]),
-            HashMap::from([
-                (
-                    StandardCommunity::new(3491, 100),
-                    PeerLocation::NorthAmerica,
-                ),
-                (
-                    StandardCommunity::new(3491, 200),
-                    PeerLocation::NorthAmerica,
-                ),
-                (StandardCommunity::new(3491, 300), PeerLocation::Europe),
-                (StandardCommunity::new(3491, 400), PeerLocation::AsiaPac),
-                (StandardCommunity::new(3491, 500), PeerLocation::Africa),
-                (StandardCommunity::new(3491, 700), PeerLocation::AsiaPac),
-                (
-                    StandardCommunity::new(3491, 1000),
-                    PeerLocation::NorthAmerica,
-                ),
-                (
-                    StandardCommunity::new(3491, 2000),
-                    PeerLocation::NorthAmerica,
-                ),
-                (StandardCommunity::new(3491, 3000), PeerLocation::Europe),
-                (StandardCommunity::new(3491, 4000), PeerLocation::AsiaPac),
-                (StandardCommunity::new(3491, 5000), PeerLocation::Africa),
-                (StandardCommunity::new(3491, 7000), PeerLocation::AsiaPac),
-            ]),
-        ),
+    let mut cm = CommMappings::new(
+        HashMap::from([
+            (StandardCommunity::new(3491, 9001), PeerType::Customer),
+            (StandardCommunity::new(3491, 9002), PeerType::Peer),
+        ]),
+        HashMap::from([
+            (StandardCommunity::new(3491, 300), Continent::Europe),
+            (StandardCommunity::new(3491, 400), Continent::AsiaPac),
+            (StandardCommunity::new(3491, 500), Continent::Africa),
+            (StandardCommunity::new(3491, 3000), Continent::Europe),
+            (StandardCommunity::new(3491, 4000), Continent::AsiaPac),
+            (StandardCommunity::new(3491, 5000), Continent::Africa),
+        ]),
     );
 
+    // Customers (N00) and peers (N000) on the east coast, west coast and in Australia
+    cm.add_peer_location(matcher("3491:100"), location("NorthAmerica/UsEastCoast"));
+    cm.add_peer_location(matcher("3491:1000"), location("NorthAmerica/UsEastCoast"));
+    cm.add_peer_location(matcher("3491:200"), location("NorthAmerica/UsWestCoast"));
+    cm.add_peer_location(matcher("3491:2000"), location("NorthAmerica/UsWestCoast"));
+    cm.add_peer_location(matcher("3491:700"), location("AsiaPac/Oceania/AU"));
+    cm.add_peer_location(matcher("3491:7000"), location("AsiaPac/Oceania/AU"));
+
+    // Learned from customers (N00) and peers (N000), where N is the region
+    cm.add_peer_type(matcher("3491:[1-57]00"), PeerType::Customer);
+    cm.add_peer_type(matcher("3491:[1-57]000"), PeerType::Peer);
+    asn_mappings.insert(MrtAsn::from_u32(3491), cm);
+
     /* Orange */
     asn_mappings.insert(
         MrtAsn::from_u32(5511),
@@ -367,167 +238,129 @@ pub fn insert_comm_mapping(asn_mappings: &mut HashMap<MrtAsn, CommMappings>) {
                 // (StandardCommunity::new(5511, 680), PeerType::Customer),
             ]),
             HashMap::from([
-                (
-                    StandardCommunity::new(5511, 30100),
-                    PeerLocation::NorthAmerica,
-                ),
-                (
-                    StandardCommunity::new(5511, 560),
-                    PeerLocation::NorthAmerica,
-                ),
-                (
-                    StandardCommunity::new(5511, 700),
-                    PeerLocation::NorthAmerica,
-                ),
-                (StandardCommunity::new(5511, 30106), PeerLocation::Africa),
-                (StandardCommunity::new(5511, 640), PeerLocation::Africa),
-                (StandardCommunity::new(5511, 730), PeerLocation::Africa),
-                (StandardCommunity::new(5511, 30121), PeerLocation::AsiaPac),
-                (StandardCommunity::new(5511, 600), PeerLocation::AsiaPac),
-                (StandardCommunity::new(5511, 720), PeerLocation::AsiaPac),
-                (StandardCommunity::new(5511, 30139), PeerLocation::Europe),
-                (StandardCommunity::new(5511, 500), PeerLocation::Europe),
-                (StandardCommunity::new(5511, 710), PeerLocation::Europe),
-                (StandardCommunity::new(5511, 30173), PeerLocation::Africa),
-                (StandardCommunity::new(5511, 30184), PeerLocation::Africa),
-                (
-                    StandardCommunity::new(5511, 30194),
-                    PeerLocation::NorthAmerica,
-                ),
-                (
-                    StandardCommunity::new(5511, 540),
-                    PeerLocation::NorthAmerica,
-                ),
-                (
-                    StandardCommunity::new(5511, 30218),
-                    PeerLocation::NorthAmerica,
-                ),
-                (StandardCommunity::new(5511, 30228), PeerLocation::Europe),
-                (StandardCommunity::new(5511, 30237), PeerLocation::AsiaPac),
-                (StandardCommunity::new(5511, 680), PeerLocation::AsiaPac),
-                (StandardCommunity::new(5511, 30241), PeerLocation::AsiaPac),
-                (StandardCommunity::new(5511, 30251), PeerLocation::Africa),
-                (
-                    StandardCommunity::new(5511, 30541),
-                    PeerLocation::SouthAmerica,
-                ),
-                (
-                    StandardCommunity::new(5511, 590),
-                    PeerLocation::SouthAmerica,
-                ),
-                (StandardCommunity::new(5511, 30257), PeerLocation::Europe),
-                (StandardCommunity::new(5511, 30343), PeerLocation::Africa),
-                (StandardCommunity::new(5511, 30416), PeerLocation::AsiaPac),
-                (StandardCommunity::new(5511, 650), PeerLocation::AsiaPac),
-                (StandardCommunity::new(5511, 30428), PeerLocation::Europe),
+                (StandardCommunity::new(5511, 30100), Continent::NorthAmerica),
+                (StandardCommunity::new(5511, 560), Continent::NorthAmerica),
+                (StandardCommunity::new(5511, 700), Continent::NorthAmerica),
+                (StandardCommunity::new(5511, 30106), Continent::Africa),
+                (StandardCommunity::new(5511, 640), Continent::Africa),
+                (StandardCommunity::new(5511, 730), Continent::Africa),
+                (StandardCommunity::new(5511, 30121), Continent::AsiaPac),
+                (StandardCommunity::new(5511, 600), Continent::AsiaPac),
+                (StandardCommunity::new(5511, 720), Continent::AsiaPac),
+                (StandardCommunity::new(5511, 30139), Continent::Europe),
+                (StandardCommunity::new(5511, 500), Continent::Europe),
+                (StandardCommunity::new(5511, 710), Continent::Europe),
+                (StandardCommunity::new(5511, 30173), Continent::Africa),
+                (StandardCommunity::new(5511, 30184), Continent::Africa),
+                (StandardCommunity::new(5511, 30194), Continent::NorthAmerica),
+                (StandardCommunity::new(5511, 540), Continent::NorthAmerica),
+                (StandardCommunity::new(5511, 30218), Continent::NorthAmerica),
+                (StandardCommunity::new(5511, 30228), Continent::Europe),
+                (StandardCommunity::new(5511, 30237), Continent::AsiaPac),
+                (StandardCommunity::new(5511, 680), Continent::AsiaPac),
+                (StandardCommunity::new(5511, 30241), Continent::AsiaPac),
+                (StandardCommunity::new(5511, 30251), Continent::Africa),
+                (StandardCommunity::new(5511, 30541), Continent::SouthAmerica),
+                (StandardCommunity::new(5511, 590), Continent::SouthAmerica),
+                (StandardCommunity::new(5511, 30257), Continent::Europe),
+                (StandardCommunity::new(5511, 30343), Continent::Africa),
+                (StandardCommunity::new(5511, 30416), Continent::AsiaPac),
+                (StandardCommunity::new(5511, 650), Continent::AsiaPac),
+                (StandardCommunity::new(5511, 30428), Continent::Europe),
             ]),
         ),
     );
 
     /* TATA */
-    asn_mappings.insert(
-        MrtAsn::from_u32(6453),
-        CommMappings::new(
-            HashMap::from([
-                (StandardCommunity::new(6453, 50), PeerType::Customer),
-                (StandardCommunity::new(6453, 86), PeerType::Peer),
-            ]),
-            HashMap::from([
-                (
-                    StandardCommunity::new(6453, 1000),
-                    PeerLocation::NorthAmerica,
-                ),
-                (StandardCommunity::new(6453, 2000), PeerLocation::Europe),
-                (StandardCommunity::new(6453, 3000), PeerLocation::AsiaPac),
-                (StandardCommunity::new(6453, 4000), PeerLocation::Africa),
-                (StandardCommunity::new(6453, 6000), PeerLocation::AsiaPac),
-            ]),
-        ),
+    let mut cm = CommMappings::new(
+        HashMap::from([
+            (StandardCommunity::new(6453, 50), PeerType::Customer),
+            (StandardCommunity::new(6453, 86), PeerType::Peer),
+        ]),
+        HashMap::from([
+            (StandardCommunity::new(6453, 1000), Continent::NorthAmerica),
+            (StandardCommunity::new(6453, 2000), Continent::Europe),
+            (StandardCommunity::new(6453, 3000), Continent::AsiaPac),
+            (StandardCommunity::new(6453, 4000), Continent::Africa),
+        ]),
     );
 
+    cm.add_peer_location(matcher("6453:6000"), location("AsiaPac/SouthAsia/IN"));
+    asn_mappings.insert(MrtAsn::from_u32(6453), cm);
+
     /* Zayo */
-    asn_mappings.insert(
-        MrtAsn::from_u32(6461),
-        CommMappings::new(
-            HashMap::from([
-                (StandardCommunity::new(6461, 2101), PeerType::Customer),
-                (StandardCommunity::new(6461, 2601), PeerType::Peer),
-                (StandardCommunity::new(6461, 5994), PeerType::Peer),
-                (StandardCommunity::new(6461, 5995), PeerType::Peer),
-                (StandardCommunity::new(6461, 5996), PeerType::Peer),
-                (StandardCommunity::new(6461, 5997), PeerType::Peer),
-                (StandardCommunity::new(6461, 5998), PeerType::Customer),
-            ]),
-            HashMap::from([
-                (
-                    StandardCommunity::new(6461, 2101),
-                    PeerLocation::NorthAmerica,
-                ),
-                (
-                    StandardCommunity::new(6461, 2601),
-                    PeerLocation::NorthAmerica,
-                ),
-                (StandardCommunity::new(6461, 5994), PeerLocation::AsiaPac),
-                (StandardCommunity::new(6461, 5996), PeerLocation::Europe),
-            ]),
-        ),
+    let mut cm = CommMappings::new(
+        HashMap::from([
+            (StandardCommunity::new(6461, 2101), PeerType::Customer),
+            (StandardCommunity::new(6461, 2601), PeerType::Peer),
+            (StandardCommunity::new(6461, 5994), PeerType::Peer),
+            (StandardCommunity::new(6461, 5995), PeerType::Peer),
+            (StandardCommunity::new(6461, 5996), PeerType::Peer),
+            (StandardCommunity::new(6461, 5997), PeerType::Peer),
+            (StandardCommunity::new(6461, 5998), PeerType::Customer),
+        ]),
+        HashMap::from([
+            (StandardCommunity::new(6461, 5994), Continent::AsiaPac),
+            (StandardCommunity::new(6461, 5996), Continent::Europe),
+        ]),
     );
 
+    // Customers and peers in the USA
+    cm.add_peer_location(matcher("6461:2101"), location("NorthAmerica//US"));
+    cm.add_peer_location(matcher("6461:2601"), location("NorthAmerica//US"));
+    cm.add_session_type(matcher("6461:5995"), SessionType::PublicIxp);
+    asn_mappings.insert(MrtAsn::from_u32(6461), cm);
+
     /* TISparkle */
     asn_mappings.insert(
         MrtAsn::from_u32(6762),
         CommMappings::new(
             HashMap::from([(StandardCommunity::new(6762, 40), PeerType::Customer)]),
             HashMap::from([
-                (StandardCommunity::new(6762, 30), PeerLocation::Europe),
-                (StandardCommunity::new(6762, 31), PeerLocation::NorthAmerica),
-                (StandardCommunity::new(6762, 32), PeerLocation::SouthAmerica),
-                (StandardCommunity::new(6762, 33), PeerLocation::AsiaPac),
-                (StandardCommunity::new(6762, 34), PeerLocation::Africa),
+                (StandardCommunity::new(6762, 30), Continent::Europe),
+                (StandardCommunity::new(6762, 31), Continent::NorthAmerica),
+                (StandardCommunity::new(6762, 32), Continent::SouthAmerica),
+                (StandardCommunity::new(6762, 33), Continent::AsiaPac),
+                (StandardCommunity::new(6762, 34), Continent::Africa),
             ]),
         ),
     );
 
     /* Libery Global */
-    asn_mappings.insert(
-        MrtAsn::from_u32(6830),
-        CommMappings::new(
-            HashMap::from([
-                (StandardCommunity::new(6830, 13000), PeerType::Customer),
-                (StandardCommunity::new(6830, 16000), PeerType::Peer),
-                (StandardCommunity::new(6830, 17000), PeerType::Peer),
-            ]),
-            HashMap::new(),
-        ),
+    let mut cm = CommMappings::new(
+        HashMap::from([
+            (StandardCommunity::new(6830, 13000), PeerType::Customer),
+            (StandardCommunity::new(6830, 16000), PeerType::Peer),
+            (StandardCommunity::new(6830, 17000), PeerType::Peer),
+        ]),
+        HashMap::new(),
     );
 
+    // Learned from public peers via an IXP, and from private peers
+    cm.add_session_type(matcher("6830:16000"), SessionType::PublicIxp);
+    cm.add_session_type(matcher("6830:17000"), SessionType::PrivateInterconnect);
+    asn_mappings.insert(MrtAsn::from_u32(6830), cm);
+
     /* Hurricane Electric */
-    asn_mappings.insert(
-        MrtAsn::from_u32(6939),
-        CommMappings::new(
-            HashMap::from([
-                (StandardCommunity::new(6939, 1000), PeerType::Customer),
-                (StandardCommunity::new(6939, 16000), PeerType::Peer),
-                (StandardCommunity::new(6939, 17000), PeerType::Peer),
-            ]),
-            HashMap::from([
-                (
-                    StandardCommunity::new(6939, 9001),
-                    PeerLocation::NorthAmerica,
-                ),
-                (StandardCommunity::new(6939, 9002), PeerLocation::Europe),
-                (StandardCommunity::new(6939, 9003), PeerLocation::AsiaPac),
-                (StandardCommunity::new(6939, 9004), PeerLocation::Africa),
-                (
-                    StandardCommunity::new(6939, 9005),
-                    PeerLocation::SouthAmerica,
-                ),
-                (StandardCommunity::new(6939, 9006), PeerLocation::AsiaPac),
-                (StandardCommunity::new(6939, 9007), PeerLocation::MiddleEast),
-            ]),
-        ),
+    let mut cm = CommMappings::new(
+        HashMap::from([
+            (StandardCommunity::new(6939, 1000), PeerType::Customer),
+            (StandardCommunity::new(6939, 16000), PeerType::Peer),
+            (StandardCommunity::new(6939, 17000), PeerType::Peer),
+        ]),
+        HashMap::from([
+            (StandardCommunity::new(6939, 9001), Continent::NorthAmerica),
+            (StandardCommunity::new(6939, 9002), Continent::Europe),
+            (StandardCommunity::new(6939, 9003), Continent::AsiaPac),
+            (StandardCommunity::new(6939, 9004), Continent::Africa),
+            (StandardCommunity::new(6939, 9005), Continent::SouthAmerica),
+            (StandardCommunity::new(6939, 9007), Continent::MiddleEast),
+        ]),
     );
 
+    cm.add_peer_location(matcher("6939:9006"), location("AsiaPac/Oceania"));
+    asn_mappings.insert(MrtAsn::from_u32(6939), cm);
+
     /* AT&T */
     asn_mappings.insert(
         MrtAsn::from_u32(7018),
@@ -550,17 +383,11 @@ pub fn insert_comm_mapping(asn_mappings: &mut HashMap<MrtAsn, CommMappings>) {
                 (StandardCommunity::new(12956, 322), PeerType::PaidPeer),
             ]),
             HashMap::from([
-                (StandardCommunity::new(12956, 4001), PeerLocation::Europe),
-                (
-                    StandardCommunity::new(12956, 4002),
-                    PeerLocation::SouthAmerica,
-                ),
-                (
-                    StandardCommunity::new(12956, 4003),
-                    PeerLocation::NorthAmerica,
-                ),
-                (StandardCommunity::new(12956, 4004), PeerLocation::AsiaPac),
-                (StandardCommunity::new(12956, 4005), PeerLocation::Africa),
+                (StandardCommunity::new(12956, 4001), Continent::Europe),
+                (StandardCommunity::new(12956, 4002), Continent::SouthAmerica),
+                (StandardCommunity::new(12956, 4003), Continent::NorthAmerica),
+                (StandardCommunity::new(12956, 4004), Continent::AsiaPac),
+                (StandardCommunity::new(12956, 4005), Continent::Africa),
             ]),
         ),
     );
@@ -1,38 +1,59 @@
 pub mod community_mappings {
     use crate::comm_data::insert_comm_mapping;
+    use crate::comm_matchers::community_matchers::{CommunityMatcher, CommunityMatchers};
+    use crate::dates::ymd::Ymd;
     use crate::mrt_asn::asn::MrtAsn;
     use crate::mrt_communities::standard_communities::StandardCommunity;
-    use crate::peer_attrs::peer_data::{PeerLocation, PeerType};
+    use crate::peer_attrs::peer_data::{Continent, PeerLocation, PeerType, SessionType};
     use std::collections::HashMap;
 
     #[derive(Debug, PartialEq, Eq)]
     pub struct CommMappings {
-        peer_type: HashMap<StandardCommunity, PeerType>,
-        peer_location: HashMap<StandardCommunity, PeerLocation>,
+        peer_type: CommunityMatchers<PeerType>,
+        peer_location: CommunityMatchers<PeerLocation>,
+        session_type: CommunityMatchers<SessionType>,
+        neighbor: CommunityMatchers<MrtAsn>,
     }
 
-    /// Map community values to peer type and peer location
+    /// Map community values to peer type, peer location, session type and the
+    /// specific neighbor a route was learned from
     impl CommMappings {
         pub fn new(
             peer_type: HashMap<StandardCommunity, PeerType>,
-            peer_location: HashMap<StandardCommunity, PeerLocation>,
+            peer_location: HashMap<StandardCommunity, Continent>,
         ) -> CommMappings {
             CommMappings {
-                peer_type,
-                peer_location,
+                peer_type: CommunityMatchers::from(peer_type),
+                peer_location: CommunityMatchers::from(
+                    peer_location
+                        .into_iter()
+                        .map(|(community, continent)| (community, PeerLocation::from(continent)))
+                        .collect::<HashMap<StandardCommunity, PeerLocation>>(),
+                ),
+                session_type: CommunityMatchers::default(),
+                neighbor: CommunityMatchers::default(),
             }
         }
 
         pub fn add_peer_location(
             &mut self,
-            community: StandardCommunity,
+            matcher: CommunityMatcher,
             peer_location: PeerLocation,
         ) {
-            self.peer_location.insert(community, peer_location);
+            self.peer_location.insert(matcher, peer_location);
         }
 
-        pub fn add_peer_type(&mut self, community: StandardCommunity, peer_type: PeerType) {
-            self.peer_type.insert(community, peer_type);
+        pub fn add_peer_type(&mut self, matcher: CommunityMatcher, peer_type: PeerType) {
+            self.peer_type.insert(matcher, peer_type);
+        }
+
+        pub fn add_session_type(&mut self, matcher: CommunityMatcher, session_type: SessionType) {
+            self.session_type.insert(matcher, session_type);
+        }
+
+        /// Map a community which means the route was learned from a specific neighbor ASN
+        pub fn add_neighbor(&mut self, matcher: CommunityMatcher, neighbor: MrtAsn) {
+            self.neighbor.insert(matcher, neighbor);
         }
 
         pub fn get_peer_type(&self, community: &StandardCommunity) -> Option<&PeerType> {
@@ -42,49 +63,280 @@ pub mod community_mappings {
         pub fn get_peer_location(&self, community: &StandardCommunity) -> Option<&PeerLocation> {
             self.peer_location.get(community)
         }
+
+        pub fn get_session_type(&self, community: &StandardCommunity) -> Option<&SessionType> {
+            self.session_type.get(community)
+        }
+
+        pub fn get_neighbor(&self, community: &StandardCommunity) -> Option<&MrtAsn> {
+            self.neighbor.get(community)
+        }
+
+        pub fn get_peer_type_matchers(&self) -> &CommunityMatchers<PeerType> {
+            &self.peer_type
+        }
+
+        pub fn get_peer_location_matchers(&self) -> &CommunityMatchers<PeerLocation> {
+            &self.peer_location
+        }
+
+        pub fn get_session_type_matchers(&self) -> &CommunityMatchers<SessionType> {
+            &self.session_type
+        }
+
+        pub fn get_neighbor_matchers(&self) -> &CommunityMatchers<MrtAsn> {
+            &self.neighbor
+        }
+
+        /// Check if the community is mapped to a peer type, location, session type or neighbor
+        pub fn has_community(&self, community: &StandardCommunity) -> bool {
+            self.get_peer_type(community).is_some()
+                || self.get_peer_location(community).is_some()
+                || self.get_session_type(community).is_some()
+                || self.get_neighbor(community).is_some()
+        }
     }
 
-    /// Map ASNs to community sets
+    /// A version of an ASN's community mappings, and the unix timestamps it is valid
+    /// from (inclusive) and until (exclusive). None means valid since, or until, forever.
+    #[derive(Debug, PartialEq, Eq)]
+    struct MappingVersion {
+        valid_from: Option<u64>,
+        valid_until: Option<u64>,
+        mappings: CommMappings,
+    }
+
+    impl MappingVersion {
+        fn is_valid_at(&self, timestamp: u64) -> bool {
+            self.valid_from.is_none_or(|from| from <= timestamp)
+                && self.valid_until.is_none_or(|until| timestamp < until)
+        }
+    }
+
+    /// Map ASNs to community sets. Operators renumber their communities over time,
+    /// so each ASN can have several versions of its mappings, and lookups use the
+    /// version in force at the time the RIB was dumped.
     #[derive(Debug, PartialEq, Eq)]
     pub struct AsnMappings {
-        asn_mappings: HashMap<MrtAsn, CommMappings>,
+        asn_mappings: HashMap<MrtAsn, Vec<MappingVersion>>,
     }
 
     impl Default for AsnMappings {
         fn default() -> Self {
-            let mut asn_mappings = HashMap::<MrtAsn, CommMappings>::new();
+            let mut asn_mappings = Self::new(HashMap::new());
             insert_comm_mapping(&mut asn_mappings);
-            Self::new(asn_mappings)
+            asn_mappings
         }
     }
 
     impl AsnMappings {
         pub fn new(asn_mappings: HashMap<MrtAsn, CommMappings>) -> AsnMappings {
-            AsnMappings { asn_mappings }
+            let mut versioned = AsnMappings {
+                asn_mappings: HashMap::new(),
+            };
+            for (asn, community_mappings) in asn_mappings {
+                versioned.insert(asn, community_mappings);
+            }
+            versioned
+        }
+
+        /// Add mappings for an ASN which are valid at all times
+        pub fn insert(&mut self, asn: MrtAsn, community_mappings: CommMappings) {
+            self.insert_between(asn, None, None, community_mappings);
+        }
+
+        /// Add a version of an ASN's mappings, valid from the start of one day
+        /// until the start of another. Either end may be left open.
+        pub fn insert_between(
+            &mut self,
+            asn: MrtAsn,
+            valid_from: Option<Ymd>,
+            valid_until: Option<Ymd>,
+            community_mappings: CommMappings,
+        ) {
+            self.asn_mappings
+                .entry(asn)
+                .or_default()
+                .push(MappingVersion {
+                    valid_from: valid_from.map(|date| date.to_unix_ts()),
+                    valid_until: valid_until.map(|date| date.to_unix_ts()),
+                    mappings: community_mappings,
+                });
+        }
+
+        /// Every version of every ASN's mappings, with the timestamps it is valid from and
+        /// until. The versions of each ASN are returned together, in the order they were inserted.
+        pub fn get_versions(
+            &self,
+        ) -> impl Iterator<Item = (&MrtAsn, Option<u64>, Option<u64>, &CommMappings)> {
+            self.asn_mappings.iter().flat_map(|(asn, versions)| {
+                versions.iter().map(move |version| {
+                    (
+                        asn,
+                        version.valid_from,
+                        version.valid_until,
+                        &version.mappings,
+                    )
+                })
+            })
+        }
+
+        pub fn has_asn(&self, asn: &MrtAsn) -> bool {
+            self.asn_mappings.contains_key(asn)
+        }
+
+        /// The version of an ASN's mappings in force at the timestamp. Where versions
+        /// overlap, the one which became valid most recently is used.
+        fn get_mappings(&self, asn: &MrtAsn, timestamp: u32) -> Option<&CommMappings> {
+            self.asn_mappings
+                .get(asn)?
+                .iter()
+                .filter(|version| version.is_valid_at(timestamp as u64))
+                .max_by_key(|version| version.valid_from)
+                .map(|version| &version.mappings)
         }
 
         pub fn get_asn_peer_location(
             &self,
             asn: &MrtAsn,
             community: &StandardCommunity,
+            timestamp: u32,
         ) -> Option<&PeerLocation> {
-            if self.asn_mappings.contains_key(asn) {
-                let community_mappings = self.asn_mappings.get(asn).unwrap();
-                return community_mappings.get_peer_location(community);
-            }
-            None
+            self.get_mappings(asn, timestamp)
+                .and_then(|community_mappings| community_mappings.get_peer_location(community))
         }
 
         pub fn get_asn_peer_type(
             &self,
             asn: &MrtAsn,
             community: &StandardCommunity,
+            timestamp: u32,
         ) -> Option<&PeerType> {
-            if self.asn_mappings.contains_key(asn) {
-                let community_mappings = self.asn_mappings.get(asn).unwrap();
-                return community_mappings.get_peer_type(community);
-            }
-            None
+            self.get_mappings(asn, timestamp)
+                .and_then(|community_mappings| community_mappings.get_peer_type(community))
+        }
+
+        pub fn get_asn_session_type(
+            &self,
+            asn: &MrtAsn,
+            community: &StandardCommunity,
+            timestamp: u32,
+        ) -> Option<&SessionType> {
+            self.get_mappings(asn, timestamp)
+                .and_then(|community_mappings| community_mappings.get_session_type(community))
+        }
+
+        pub fn get_asn_neighbor(
+            &self,
+            asn: &MrtAsn,
+            community: &StandardCommunity,
+            timestamp: u32,
+        ) -> Option<&MrtAsn> {
+            self.get_mappings(asn, timestamp)
+                .and_then(|community_mappings| community_mappings.get_neighbor(community))
+        }
+
+        /// Check if an ASN has a mapping for the community, regardless of the community's ASN field
+        pub fn has_mapping(
+            &self,
+            asn: &MrtAsn,
+            community: &StandardCommunity,
+            timestamp: u32,
+        ) -> bool {
+            self.get_mappings(asn, timestamp)
+                .is_some_and(|community_mappings| community_mappings.has_community(community))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn peer_type(peer_type: PeerType) -> CommMappings {
+            CommMappings::new(
+                HashMap::from([(StandardCommunity::new(174, 1), peer_type)]),
+                HashMap::new(),
+            )
+        }
+
+        fn date(date: &str) -> Option<Ymd> {
+            Some(Ymd::parse(date).unwrap())
+        }
+
+        fn ts(date: &str) -> u32 {
+            Ymd::parse(date).unwrap().to_unix_ts() as u32
+        }
+
+        #[test]
+        fn selects_the_version_valid_at_the_timestamp() {
+            let asn = MrtAsn::from_u32(174);
+            let mut asn_mappings = AsnMappings::new(HashMap::new());
+            asn_mappings.insert_between(
+                asn.clone(),
+                None,
+                date("2024-06-01"),
+                peer_type(PeerType::Customer),
+            );
+            asn_mappings.insert_between(
+                asn.clone(),
+                date("2024-06-01"),
+                None,
+                peer_type(PeerType::Peer),
+            );
+
+            let get = |timestamp| {
+                asn_mappings
+                    .get_asn_peer_type(&asn, &StandardCommunity::new(174, 1), timestamp)
+                    .cloned()
+            };
+            assert_eq!(get(ts("2020-01-01")), Some(PeerType::Customer));
+            assert_eq!(get(ts("2024-06-01") - 1), Some(PeerType::Customer));
+            // Versions are valid from the start of their first day, until the start of their last
+            assert_eq!(get(ts("2024-06-01")), Some(PeerType::Peer));
+            assert_eq!(get(ts("2026-02-04")), Some(PeerType::Peer));
+        }
+
+        #[test]
+        fn prefers_the_most_recently_valid_of_overlapping_versions() {
+            let asn = MrtAsn::from_u32(174);
+            let mut asn_mappings = AsnMappings::new(HashMap::new());
+            asn_mappings.insert(asn.clone(), peer_type(PeerType::Customer));
+            asn_mappings.insert_between(
+                asn.clone(),
+                date("2025-01-01"),
+                date("2025-02-01"),
+                peer_type(PeerType::Upstream),
+            );
+
+            let get = |timestamp| {
+                asn_mappings
+                    .get_asn_peer_type(&asn, &StandardCommunity::new(174, 1), timestamp)
+                    .cloned()
+            };
+            assert_eq!(get(ts("2024-12-31")), Some(PeerType::Customer));
+            assert_eq!(get(ts("2025-01-15")), Some(PeerType::Upstream));
+            assert_eq!(get(ts("2025-02-01")), Some(PeerType::Customer));
+        }
+
+        #[test]
+        fn has_no_mappings_outside_every_version() {
+            let asn = MrtAsn::from_u32(174);
+            let mut asn_mappings = AsnMappings::new(HashMap::new());
+            asn_mappings.insert_between(
+                asn.clone(),
+                date("2025-01-01"),
+                None,
+                peer_type(PeerType::Peer),
+            );
+
+            let community = StandardCommunity::new(174, 1);
+            assert!(!asn_mappings.has_mapping(&asn, &community, ts("2024-12-31")));
+            assert!(asn_mappings.has_mapping(&asn, &community, ts("2025-01-01")));
+            assert!(!asn_mappings.has_mapping(
+                &MrtAsn::from_u32(3356),
+                &community,
+                ts("2025-01-01")
+            ));
         }
     }
 }
@@ -0,0 +1,594 @@
+pub mod community_matchers {
+    use crate::mrt_communities::standard_communities::StandardCommunity;
+    use crate::parse_errors::parse_error::ParseError;
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::str::FromStr;
+
+    // Bitset of the digits 0-9, used for a pattern position which matches any digit
+    const ANY_DIGIT: u16 = 0x3ff;
+
+    /// Matches standard communities with a given ASN, by value. Parsed from strings of the form:
+    ///
+    /// * `174:21000` - an exact value
+    /// * `1273:11000-18999` - an inclusive range of values
+    /// * `65000:0x1000/0xf000` - values whose bits under the mask (after the `/`) equal the value
+    /// * `1273:1xxxx`, `3491:[1-7]000` - a decimal pattern, where `x` matches any digit and
+    ///   `[...]` matches a set of digits. Values must have as many digits as the pattern.
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub enum CommunityMatcher {
+        Exact(StandardCommunity),
+        Range { asn: u32, start: u16, end: u16 },
+        Mask { asn: u32, value: u16, mask: u16 },
+        Pattern { asn: u32, digits: Vec<u16> },
+    }
+
+    impl From<StandardCommunity> for CommunityMatcher {
+        fn from(community: StandardCommunity) -> Self {
+            Self::Exact(community)
+        }
+    }
+
+    impl FromStr for CommunityMatcher {
+        type Err = ParseError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let invalid = || ParseError::InvalidCommunity(s.to_owned());
+            let (asn, value) = s.split_once(':').ok_or_else(invalid)?;
+            let asn = asn.trim().parse::<u32>().map_err(|_| invalid())?;
+            let value = value.trim();
+
+            let is_hex = value.starts_with("0x");
+            if let Some((value, mask)) = value.split_once('/') {
+                let value = Self::parse_value(value).ok_or_else(invalid)?;
+                let mask = Self::parse_value(mask).ok_or_else(invalid)?;
+                if value & !mask != 0 {
+                    return Err(invalid());
+                }
+                Ok(Self::Mask { asn, value, mask })
+            } else if value.contains('[') || (!is_hex && value.contains(['x', 'X'])) {
+                let digits = Self::parse_pattern(value).ok_or_else(invalid)?;
+                Ok(Self::Pattern { asn, digits })
+            } else if let Some((start, end)) = value.split_once('-') {
+                let start = Self::parse_value(start).ok_or_else(invalid)?;
+                let end = Self::parse_value(end).ok_or_else(invalid)?;
+                if start > end {
+                    return Err(invalid());
+                }
+                Ok(Self::Range { asn, start, end })
+            } else {
+                let value = Self::parse_value(value).ok_or_else(invalid)?;
+                Ok(Self::Exact(StandardCommunity::new(asn, value)))
+            }
+        }
+    }
+
+    impl fmt::Display for CommunityMatcher {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Self::Exact(community) => {
+                    write!(f, "{}:{}", community.get_asn(), community.get_value())
+                }
+                Self::Range { asn, start, end } => write!(f, "{}:{}-{}", asn, start, end),
+                Self::Mask { asn, value, mask } => write!(f, "{}:{:#x}/{:#x}", asn, value, mask),
+                Self::Pattern { asn, digits } => {
+                    write!(f, "{}:", asn)?;
+                    for allowed in digits {
+                        write!(f, "{}", Self::format_digits(*allowed))?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    impl CommunityMatcher {
+        pub fn get_asn(&self) -> u32 {
+            match self {
+                Self::Exact(community) => community.get_asn().clone().to_u32(),
+                Self::Range { asn, .. } | Self::Mask { asn, .. } | Self::Pattern { asn, .. } => {
+                    *asn
+                }
+            }
+        }
+
+        /// The values matched, as sorted and non-overlapping inclusive ranges
+        pub fn get_value_ranges(&self) -> Vec<(u16, u16)> {
+            match self {
+                Self::Exact(community) => {
+                    Vec::from([(*community.get_value(), *community.get_value())])
+                }
+                Self::Range { start, end, .. } => Vec::from([(*start, *end)]),
+                Self::Pattern { digits, .. } => Self::get_pattern_ranges(digits),
+                Self::Mask { value, mask, .. } => {
+                    let mut ranges = Vec::<(u16, u16)>::new();
+                    for v in (0..=u16::MAX).filter(|v| v & mask == *value) {
+                        match ranges.last_mut() {
+                            Some((_, end)) if *end + 1 == v => *end = v,
+                            _ => ranges.push((v, v)),
+                        }
+                    }
+                    ranges
+                }
+            }
+        }
+
+        /// Parse a decimal or 0x prefixed hex community value
+        fn parse_value(value: &str) -> Option<u16> {
+            let value = value.trim();
+            match value.strip_prefix("0x") {
+                Some(hex) => u16::from_str_radix(hex, 16).ok(),
+                None => value.parse::<u16>().ok(),
+            }
+        }
+
+        /// Parse a pattern into the set of digits allowed at each position
+        fn parse_pattern(pattern: &str) -> Option<Vec<u16>> {
+            let mut digits = Vec::<u16>::new();
+            let mut chars = pattern.chars();
+            while let Some(c) = chars.next() {
+                let allowed = match c {
+                    'x' | 'X' => ANY_DIGIT,
+                    '0'..='9' => 1 << c.to_digit(10)?,
+                    '[' => {
+                        let (set, rest) = chars.as_str().split_once(']')?;
+                        chars = rest.chars();
+                        Self::parse_digit_set(set)?
+                    }
+                    _ => return None,
+                };
+                digits.push(allowed);
+            }
+
+            // Community values are at most 5 digits long
+            if digits.is_empty() || digits.len() > 5 {
+                return None;
+            }
+            Some(digits)
+        }
+
+        /// Parse the inside of a [...] digit set, e.g. "1-7" or "135"
+        fn parse_digit_set(set: &str) -> Option<u16> {
+            let chars: Vec<u32> = set
+                .chars()
+                .map(|c| {
+                    if c == '-' {
+                        Some(u32::MAX)
+                    } else {
+                        c.to_digit(10)
+                    }
+                })
+                .collect::<Option<Vec<u32>>>()?;
+
+            let mut allowed = 0u16;
+            let mut i = 0;
+            while i < chars.len() {
+                if i + 2 < chars.len() && chars[i + 1] == u32::MAX {
+                    let (start, end) = (chars[i], chars[i + 2]);
+                    if start == u32::MAX || end == u32::MAX || start > end {
+                        return None;
+                    }
+                    for digit in start..=end {
+                        allowed |= 1 << digit;
+                    }
+                    i += 3;
+                } else if chars[i] == u32::MAX {
+                    return None;
+                } else {
+                    allowed |= 1 << chars[i];
+                    i += 1;
+                }
+            }
+            (allowed != 0).then_some(allowed)
+        }
+
+        fn format_digits(allowed: u16) -> String {
+            if allowed == ANY_DIGIT {
+                return String::from("x");
+            }
+            let digits: Vec<u32> = (0..10).filter(|d| allowed & (1 << d) != 0).collect();
+            if digits.len() == 1 {
+                return digits[0].to_string();
+            }
+
+            // Collapse runs of consecutive digits into ranges
+            let mut set = String::new();
+            let mut i = 0;
+            while i < digits.len() {
+                let mut j = i;
+                while j + 1 < digits.len() && digits[j + 1] == digits[j] + 1 {
+                    j += 1;
+                }
+                if j - i >= 2 {
+                    set.push_str(&format!("{}-{}", digits[i], digits[j]));
+                } else {
+                    set.extend(digits[i..=j].iter().map(|d| d.to_string()));
+                }
+                i = j + 1;
+            }
+            format!("[{}]", set)
+        }
+
+        /// Expand a pattern into the ranges of values it matches, merging adjacent ranges
+        fn get_pattern_ranges(digits: &[u16]) -> Vec<(u16, u16)> {
+            // Trailing positions which match any digit become the width of each range
+            let wildcards = digits.iter().rev().take_while(|d| **d == ANY_DIGIT).count();
+            let prefix = &digits[..digits.len() - wildcards];
+            let width = 10u32.pow(wildcards as u32);
+
+            let mut prefixes = Vec::from([0u32]);
+            for allowed in prefix {
+                prefixes = prefixes
+                    .iter()
+                    .flat_map(|p| {
+                        (0..10)
+                            .filter(|d| allowed & (1 << d) != 0)
+                            .map(move |d| p * 10 + d)
+                    })
+                    .collect();
+            }
+
+            // Values must have as many digits as the pattern, so can't start with a zero
+            let min = match digits.len() {
+                1 => 0,
+                len => 10u32.pow(len as u32 - 1),
+            };
+
+            let mut ranges = Vec::<(u16, u16)>::new();
+            for p in prefixes {
+                let start = (p * width).max(min);
+                let end = (p * width + width - 1).min(u16::MAX as u32);
+                if start > end {
+                    continue;
+                }
+                match ranges.last_mut() {
+                    Some((_, last_end)) if *last_end as u32 + 1 == start => *last_end = end as u16,
+                    _ => ranges.push((start as u16, end as u16)),
+                }
+            }
+            ranges
+        }
+    }
+
+    /// A lookup from community matchers to a value, e.g. a peer type. Exact communities
+    /// are looked up in a hash map, ranges (and patterns, which are expanded to ranges
+    /// when inserted) with a binary search, and masks with a linear scan, in that order.
+    #[derive(Debug, Eq, PartialEq)]
+    pub struct CommunityMatchers<T> {
+        exact: HashMap<StandardCommunity, T>,
+        // Sorted by ASN, then the start of the range
+        ranges: Vec<(u32, u16, u16, T)>,
+        // The ranges split into non-overlapping segments, each with the index of the range
+        // which wins within it, sorted by ASN then the start of the segment
+        segments: Vec<(u32, u16, u16, usize)>,
+        masks: Vec<(u32, u16, u16, T)>,
+    }
+
+    impl<T> Default for CommunityMatchers<T> {
+        fn default() -> Self {
+            Self::from(HashMap::<StandardCommunity, T>::new())
+        }
+    }
+
+    impl<T> From<HashMap<StandardCommunity, T>> for CommunityMatchers<T> {
+        fn from(exact: HashMap<StandardCommunity, T>) -> Self {
+            Self {
+                exact,
+                ranges: Vec::new(),
+                segments: Vec::new(),
+                masks: Vec::new(),
+            }
+        }
+    }
+
+    impl<T: Clone> CommunityMatchers<T> {
+        pub fn insert(&mut self, matcher: CommunityMatcher, value: T) {
+            match matcher {
+                CommunityMatcher::Exact(community) => {
+                    self.exact.insert(community, value);
+                }
+                CommunityMatcher::Range { asn, start, end } => {
+                    self.insert_range(asn, start, end, value);
+                    self.build_segments();
+                }
+                CommunityMatcher::Mask {
+                    asn,
+                    value: v,
+                    mask,
+                } => self.masks.push((asn, v, mask, value)),
+                CommunityMatcher::Pattern { asn, digits } => {
+                    for (start, end) in CommunityMatcher::get_pattern_ranges(&digits) {
+                        self.insert_range(asn, start, end, value.clone());
+                    }
+                    self.build_segments();
+                }
+            }
+        }
+
+        fn insert_range(&mut self, asn: u32, start: u16, end: u16, value: T) {
+            let pos = self
+                .ranges
+                .partition_point(|(a, s, _, _)| (*a, *s) <= (asn, start));
+            self.ranges.insert(pos, (asn, start, end, value));
+        }
+
+        /// Split the ranges into non-overlapping segments, so that a lookup is a single
+        /// binary search. Where ranges overlap, the range starting closest to the value
+        /// wins, or the one inserted last if they start at the same value.
+        fn build_segments(&mut self) {
+            self.segments.clear();
+            let mut first = 0;
+            for ranges in self.ranges.chunk_by(|(a, ..), (b, ..)| a == b) {
+                let asn = ranges[0].0;
+                // The ranges which have started and not ended, the latest to start last
+                let mut open = Vec::<(u32, usize)>::new();
+                // The next value which isn't in a segment yet
+                let mut next = 0;
+                for (i, (_, start, end, _)) in ranges.iter().enumerate() {
+                    Self::add_segments(&mut self.segments, asn, &mut open, next, *start as u32);
+                    open.push((*end as u32, first + i));
+                    next = *start as u32;
+                }
+                Self::add_segments(
+                    &mut self.segments,
+                    asn,
+                    &mut open,
+                    next,
+                    u16::MAX as u32 + 1,
+                );
+                first += ranges.len();
+            }
+        }
+
+        /// Add segments from the next value up to, but not including, the value until,
+        /// each won by the latest open range to start, and drop the ranges ending before it
+        fn add_segments(
+            segments: &mut Vec<(u32, u16, u16, usize)>,
+            asn: u32,
+            open: &mut Vec<(u32, usize)>,
+            mut next: u32,
+            until: u32,
+        ) {
+            while let Some((end, i)) = open.last().copied() {
+                if end >= until {
+                    if next < until {
+                        segments.push((asn, next as u16, (until - 1) as u16, i));
+                    }
+                    break;
+                }
+                if next <= end {
+                    segments.push((asn, next as u16, end as u16, i));
+                    next = end + 1;
+                }
+                open.pop();
+            }
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.exact.is_empty() && self.ranges.is_empty() && self.masks.is_empty()
+        }
+
+        /// Every matcher and its value. Patterns are returned as the ranges they were expanded to.
+        pub fn get_entries(&self) -> Vec<(CommunityMatcher, &T)> {
+            let exact = self
+                .exact
+                .iter()
+                .map(|(community, v)| (CommunityMatcher::Exact(community.clone()), v));
+            let ranges = self.ranges.iter().map(|(asn, start, end, v)| {
+                let matcher = CommunityMatcher::Range {
+                    asn: *asn,
+                    start: *start,
+                    end: *end,
+                };
+                (matcher, v)
+            });
+            let masks = self.masks.iter().map(|(asn, value, mask, v)| {
+                let matcher = CommunityMatcher::Mask {
+                    asn: *asn,
+                    value: *value,
+                    mask: *mask,
+                };
+                (matcher, v)
+            });
+            exact.chain(ranges).chain(masks).collect()
+        }
+
+        /// Where ranges overlap, the range starting closest to the value wins,
+        /// so narrower ranges can be nested within wider ones
+        pub fn get(&self, community: &StandardCommunity) -> Option<&T> {
+            if let Some(value) = self.exact.get(community) {
+                return Some(value);
+            }
+
+            let asn = community.get_asn().clone().to_u32();
+            let value = *community.get_value();
+            let pos = self
+                .segments
+                .partition_point(|(a, s, _, _)| (*a, *s) <= (asn, value));
+            if let Some((a, _, end, i)) = pos.checked_sub(1).map(|pos| &self.segments[pos])
+                && *a == asn
+                && value <= *end
+            {
+                return Some(&self.ranges[*i].3);
+            }
+
+            self.masks
+                .iter()
+                .find(|(a, v, mask, _)| *a == asn && value & mask == *v)
+                .map(|(_, _, _, v)| v)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn matcher(s: &str) -> CommunityMatcher {
+            s.parse().unwrap()
+        }
+
+        #[test]
+        fn parses_exact_values() {
+            assert_eq!(
+                matcher("174:21000"),
+                CommunityMatcher::Exact(StandardCommunity::new(174, 21000))
+            );
+            assert_eq!(
+                matcher(" 65000 : 0x10 "),
+                CommunityMatcher::Exact(StandardCommunity::new(65000, 16))
+            );
+            assert_eq!(matcher("174:21000").get_value_ranges(), [(21000, 21000)]);
+        }
+
+        #[test]
+        fn parses_ranges() {
+            assert_eq!(
+                matcher("1273:11000-18999"),
+                CommunityMatcher::Range {
+                    asn: 1273,
+                    start: 11000,
+                    end: 18999
+                }
+            );
+            assert_eq!(matcher("1273:0x10-0x1f").get_value_ranges(), [(16, 31)]);
+        }
+
+        #[test]
+        fn parses_masks() {
+            assert_eq!(
+                matcher("65000:0x1000/0xf000"),
+                CommunityMatcher::Mask {
+                    asn: 65000,
+                    value: 0x1000,
+                    mask: 0xf000
+                }
+            );
+            assert_eq!(
+                matcher("65000:0x1000/0xf000").get_value_ranges(),
+                [(0x1000, 0x1fff)]
+            );
+
+            // A mask with gaps matches values spread across the whole space
+            let ranges = matcher("65000:0x1/0x8001").get_value_ranges();
+            assert_eq!(ranges.len(), 0x4000);
+            assert_eq!(ranges.first(), Some(&(1, 1)));
+            assert_eq!(ranges.last(), Some(&(0x7fff, 0x7fff)));
+        }
+
+        #[test]
+        fn parses_patterns() {
+            assert_eq!(
+                matcher("1273:1xxxx"),
+                CommunityMatcher::Pattern {
+                    asn: 1273,
+                    digits: Vec::from([1 << 1, ANY_DIGIT, ANY_DIGIT, ANY_DIGIT, ANY_DIGIT]),
+                }
+            );
+            assert_eq!(matcher("1273:1xxxx").get_value_ranges(), [(10000, 19999)]);
+            assert_eq!(
+                matcher("3491:[1-7]000").get_value_ranges(),
+                [
+                    (1000, 1000),
+                    (2000, 2000),
+                    (3000, 3000),
+                    (4000, 4000),
+                    (5000, 5000),
+                    (6000, 6000),
+                    (7000, 7000)
+                ]
+            );
+            // Adjacent ranges are merged
+            assert_eq!(
+                matcher("3491:[1-35]xx").get_value_ranges(),
+                [(100, 399), (500, 599)]
+            );
+            // Values must have as many digits as the pattern
+            assert_eq!(matcher("65000:xx").get_value_ranges(), [(10, 99)]);
+            // Values can't be more than 65535
+            assert_eq!(matcher("65000:6xxxx").get_value_ranges(), [(60000, 65535)]);
+        }
+
+        #[test]
+        fn formats_as_parsed() {
+            for s in [
+                "174:21000",
+                "1273:11000-18999",
+                "65000:0x1000/0xf000",
+                "1273:1xxxx",
+                "3491:[1-7]000",
+                "3491:[135]x[0-2]",
+            ] {
+                assert_eq!(matcher(s).to_string(), s);
+            }
+        }
+
+        #[test]
+        fn rejects_invalid_matchers() {
+            for s in [
+                "174",
+                "AS174:1",
+                "174:70000",
+                "174:200-100",
+                "174:0x3/0x1",
+                "174:1xxxxx",
+                "174:[]",
+                "174:[9-1]",
+                "174:[1-]",
+                "174:1[2-3",
+                "174:1y",
+            ] {
+                assert!(
+                    matches!(
+                        s.parse::<CommunityMatcher>(),
+                        Err(ParseError::InvalidCommunity(_))
+                    ),
+                    "{}",
+                    s
+                );
+            }
+        }
+
+        #[test]
+        fn looks_up_the_narrowest_nested_range_then_masks() {
+            let mut matchers = CommunityMatchers::<&str>::default();
+            matchers.insert(matcher("1273:1xxxx"), "wide");
+            matchers.insert(matcher("1273:12000-12999"), "narrow");
+            matchers.insert(matcher("1273:12345"), "exact");
+            matchers.insert(matcher("1273:0x8000/0x8000"), "mask");
+
+            let get = |value| matchers.get(&StandardCommunity::new(1273, value)).copied();
+            assert_eq!(get(11000), Some("wide"));
+            assert_eq!(get(12000), Some("narrow"));
+            assert_eq!(get(12345), Some("exact"));
+            assert_eq!(get(13000), Some("wide"));
+            assert_eq!(get(40000), Some("mask"));
+            assert_eq!(get(9999), None);
+            assert_eq!(matchers.get(&StandardCommunity::new(174, 12000)), None);
+        }
+
+        #[test]
+        fn overlapping_ranges_are_won_by_the_closest_start() {
+            let mut matchers = CommunityMatchers::<&str>::default();
+            matchers.insert(matcher("174:100-200"), "first");
+            matchers.insert(matcher("174:150-300"), "overlapping");
+            matchers.insert(matcher("174:150-160"), "same start");
+            matchers.insert(matcher("174:0-9"), "from zero");
+            matchers.insert(matcher("174:65000-65535"), "to the end");
+            matchers.insert(matcher("3356:120-130"), "other asn");
+
+            let get = |value| matchers.get(&StandardCommunity::new(174, value)).copied();
+            assert_eq!(get(0), Some("from zero"));
+            assert_eq!(get(10), None);
+            assert_eq!(get(149), Some("first"));
+            assert_eq!(get(150), Some("same start"));
+            assert_eq!(get(161), Some("overlapping"));
+            assert_eq!(get(250), Some("overlapping"));
+            assert_eq!(get(301), None);
+            assert_eq!(get(65535), Some("to the end"));
+            assert_eq!(
+                matchers.get(&StandardCommunity::new(3356, 125)).copied(),
+                Some("other asn")
+            );
+        }
+    }
+}
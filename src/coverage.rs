@@ -0,0 +1,221 @@
+pub mod collector_coverage {
+    use crate::file::ensure_dir;
+    use crate::mrt_asn::asn::MrtAsn;
+    use crate::mrt_communities::standard_communities::StandardCommunities;
+    use crate::mrt_peer::peer::Peer;
+    use log::{info, warn};
+    use serde::{Serialize, Serializer};
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::fs::File;
+    use std::io::BufWriter;
+    use std::net::IpAddr;
+
+    /// How far a route collector peer is from a T1 ASN in the AS path
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+    pub enum VantagePosition {
+        // The collector peer is the T1 itself
+        Direct,
+        // The T1 is the next ASN after the collector peer. As the collector peer is
+        // sharing a full table, it is most likely a customer of the T1.
+        Customer,
+        // The T1 is two or more ASNs away from the collector peer
+        Further,
+    }
+
+    impl VantagePosition {
+        pub fn from_position(pos: usize) -> Self {
+            match pos {
+                0 => Self::Direct,
+                1 => Self::Customer,
+                _ => Self::Further,
+            }
+        }
+    }
+
+    /// A BGP peer of a route collector
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    pub struct CollectorPeer {
+        asn: u32,
+        ip: IpAddr,
+    }
+
+    impl fmt::Display for CollectorPeer {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "AS{} {}", self.asn, self.ip)
+        }
+    }
+
+    impl Serialize for CollectorPeer {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl CollectorPeer {
+        pub fn from(peer: &Peer) -> Self {
+            Self {
+                asn: peer.get_asn(),
+                ip: peer.get_ip(),
+            }
+        }
+    }
+
+    /// How a single collector peer sees a T1 ASN
+    #[derive(Debug, Serialize)]
+    pub struct PeerCoverage {
+        position: VantagePosition,
+        routes: u64,
+    }
+
+    /// All collector peers at a single collector which provide paths containing a T1 ASN
+    #[derive(Debug, Default, Serialize)]
+    pub struct CollectorPeersCoverage {
+        routes: u64,
+        peers: HashMap<CollectorPeer, PeerCoverage>,
+    }
+
+    impl CollectorPeersCoverage {
+        fn add_route(&mut self, peer: CollectorPeer, position: VantagePosition) {
+            self.routes += 1;
+            let peer_coverage = self.peers.entry(peer).or_insert(PeerCoverage {
+                position,
+                routes: 0,
+            });
+            peer_coverage.position = peer_coverage.position.min(position);
+            peer_coverage.routes += 1;
+        }
+
+        fn merge(&mut self, other: CollectorPeersCoverage) {
+            self.routes += other.routes;
+            for (peer, other_coverage) in other.peers {
+                let peer_coverage = self.peers.entry(peer).or_insert(PeerCoverage {
+                    position: other_coverage.position,
+                    routes: 0,
+                });
+                peer_coverage.position = peer_coverage.position.min(other_coverage.position);
+                peer_coverage.routes += other_coverage.routes;
+            }
+        }
+    }
+
+    /// Visibility of a single T1 ASN across all route collectors
+    #[derive(Debug, Default, Serialize)]
+    pub struct T1Coverage {
+        routes: u64,
+        // Routes which carry at least one community with this T1's ASN as the prefix
+        routes_with_communities: u64,
+        collectors: HashMap<String, CollectorPeersCoverage>,
+    }
+
+    impl T1Coverage {
+        fn merge(&mut self, other: T1Coverage) {
+            self.routes += other.routes;
+            self.routes_with_communities += other.routes_with_communities;
+            for (collector, other_coverage) in other.collectors {
+                self.collectors
+                    .entry(collector)
+                    .or_default()
+                    .merge(other_coverage);
+            }
+        }
+
+        pub fn has_communities(&self) -> bool {
+            self.routes_with_communities > 0
+        }
+    }
+
+    /// Which collectors and collector peers provide visibility of each T1 ASN
+    #[derive(Debug, Serialize)]
+    pub struct CollectorCoverage {
+        t1_coverage: HashMap<MrtAsn, T1Coverage>,
+    }
+
+    impl Default for CollectorCoverage {
+        fn default() -> Self {
+            Self::new(
+                MrtAsn::get_t1_asns()
+                    .into_iter()
+                    .map(|asn| (asn, T1Coverage::default()))
+                    .collect(),
+            )
+        }
+    }
+
+    impl CollectorCoverage {
+        pub fn new(t1_coverage: HashMap<MrtAsn, T1Coverage>) -> Self {
+            Self { t1_coverage }
+        }
+
+        /// Record every T1 ASN in the AS path of a route seen from a collector peer
+        pub fn add_route(
+            &mut self,
+            collector: &str,
+            peer: &Peer,
+            as_sequence: &[MrtAsn],
+            communities: &StandardCommunities,
+        ) {
+            for (pos, asn) in as_sequence.iter().enumerate() {
+                if !asn.is_t1_asn() {
+                    continue;
+                }
+                let t1_coverage = self.t1_coverage.entry(asn.clone()).or_default();
+                t1_coverage.routes += 1;
+                if communities.has_asn(asn) {
+                    t1_coverage.routes_with_communities += 1;
+                }
+
+                if !t1_coverage.collectors.contains_key(collector) {
+                    t1_coverage
+                        .collectors
+                        .insert(collector.to_owned(), CollectorPeersCoverage::default());
+                }
+                t1_coverage
+                    .collectors
+                    .get_mut(collector)
+                    .unwrap()
+                    .add_route(
+                        CollectorPeer::from(peer),
+                        VantagePosition::from_position(pos),
+                    );
+            }
+        }
+
+        /// Merge the coverage seen in another file into this one
+        pub fn merge(&mut self, other: CollectorCoverage) {
+            for (asn, other_coverage) in other.t1_coverage {
+                self.t1_coverage
+                    .entry(asn)
+                    .or_default()
+                    .merge(other_coverage);
+            }
+        }
+
+        /// Log which T1 ASNs have no informational communities visible at all
+        pub fn log_summary(&self) {
+            for (asn, t1_coverage) in &self.t1_coverage {
+                if t1_coverage.routes == 0 {
+                    warn!("AS{} not seen in any AS path", asn);
+                } else if !t1_coverage.has_communities() {
+                    warn!(
+                        "AS{} seen in {} routes via {} collectors, but none carry AS{} communities",
+                        asn,
+                        t1_coverage.routes,
+                        t1_coverage.collectors.len(),
+                        asn
+                    );
+                }
+            }
+        }
+
+        pub fn to_file(&self, filename: &String) {
+            ensure_dir(filename);
+            let writer = BufWriter::new(File::create(filename).unwrap());
+            serde_json::to_writer_pretty(writer, self).unwrap();
+            info!("Wrote JSON to {}", filename);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+pub mod ymd {
+    use crate::parse_errors::parse_error::ParseError;
+    use chrono::{DateTime, Datelike, NaiveDate, Timelike};
+    use std::fmt;
+
+    /// A calendar date in UTC, used to build MRT archive paths
+    #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+    pub struct Ymd(NaiveDate);
+
+    impl fmt::Display for Ymd {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.0.format("%Y-%m-%d"))
+        }
+    }
+
+    impl Ymd {
+        pub fn new(year: u32, month: u32, day: u32) -> Result<Self, ParseError> {
+            (1970..=9999)
+                .contains(&year)
+                .then(|| NaiveDate::from_ymd_opt(year as i32, month, day))
+                .flatten()
+                .map(Self)
+                .ok_or(ParseError::InvalidDate(format!(
+                    "{:04}-{:02}-{:02}",
+                    year, month, day
+                )))
+        }
+
+        /// Parse either "yyyy-mm-dd" or "yyyymmdd"
+        pub fn parse(date: &str) -> Result<Self, ParseError> {
+            let digits: String = date.chars().filter(|c| *c != '-').collect();
+            if digits.len() != 8 || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return Err(ParseError::InvalidDate(date.to_owned()));
+            }
+            Self::new(
+                digits[0..4].parse().unwrap(),
+                digits[4..6].parse().unwrap(),
+                digits[6..8].parse().unwrap(),
+            )
+        }
+
+        /// Unix timestamp of midnight UTC at the start of this day
+        pub fn to_unix_ts(&self) -> u64 {
+            self.to_unix_ts_at(0)
+        }
+
+        /// Unix timestamp of a specific hour (UTC) of this day
+        pub fn to_unix_ts_at(&self, hour: u32) -> u64 {
+            self.0
+                .and_hms_opt(hour, 0, 0)
+                .expect("hours are between 0 and 23")
+                .and_utc()
+                .timestamp() as u64
+        }
+
+        /// The date of a unix timestamp, and the hour of that day
+        pub fn from_unix_ts(ts: u64) -> (Self, u32) {
+            let datetime = DateTime::from_timestamp(ts as i64, 0)
+                .expect("unix timestamps in a u64 are within chrono's range");
+            (Self(datetime.date_naive()), datetime.hour())
+        }
+
+        pub fn yyyy(&self) -> String {
+            format!("{:04}", self.0.year())
+        }
+
+        pub fn mm(&self) -> String {
+            format!("{:02}", self.0.month())
+        }
+
+        pub fn dd(&self) -> String {
+            format!("{:02}", self.0.day())
+        }
+
+        /// Replace {yyyy}, {mm}, {dd}, {hh} and {ts} placeholders in a URL template,
+        /// for a specific hour of this day
+        pub fn fill_template(&self, template: &str, hour: u32) -> String {
+            template
+                .replace("{yyyy}", &self.yyyy())
+                .replace("{mm}", &self.mm())
+                .replace("{dd}", &self.dd())
+                .replace("{hh}", &format!("{:02}", hour))
+                .replace("{ts}", &self.to_unix_ts_at(hour).to_string())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn parses_both_date_formats() {
+            assert_eq!(Ymd::parse("2026-02-04"), Ymd::new(2026, 2, 4));
+            assert_eq!(Ymd::parse("20260204"), Ymd::new(2026, 2, 4));
+            assert!(Ymd::parse("2026-2-4").is_err());
+            assert!(Ymd::parse("2026-02-30").is_err());
+            assert!(Ymd::parse("2025-02-29").is_err());
+            assert!(Ymd::parse("2024-02-29").is_ok());
+            assert!(Ymd::parse("2100-02-29").is_err());
+            assert!(Ymd::parse("2000-02-29").is_ok());
+        }
+
+        #[test]
+        fn unix_timestamps_at_month_and_year_boundaries() {
+            let ts = |date: &str| Ymd::parse(date).unwrap().to_unix_ts();
+            assert_eq!(ts("1970-01-01"), 0);
+            assert_eq!(ts("2000-03-01"), 951868800);
+            assert_eq!(ts("2024-02-29"), 1709164800);
+            assert_eq!(ts("2025-12-31"), 1767139200);
+            assert_eq!(ts("2026-01-01"), 1767225600);
+            assert_eq!(ts("2026-02-04"), 1770163200);
+        }
+
+        #[test]
+        fn round_trips_through_unix_timestamps() {
+            for date in [
+                "1970-01-01",
+                "2000-02-29",
+                "2000-03-01",
+                "2024-12-31",
+                "2025-01-01",
+                "2026-02-28",
+                "2026-03-01",
+            ] {
+                let ymd = Ymd::parse(date).unwrap();
+                assert_eq!(Ymd::from_unix_ts(ymd.to_unix_ts()), (ymd, 0));
+                assert_eq!(Ymd::from_unix_ts(ymd.to_unix_ts_at(23)), (ymd, 23));
+                // The last second of the day is still the same date
+                assert_eq!(Ymd::from_unix_ts(ymd.to_unix_ts() + 86399), (ymd, 23));
+            }
+        }
+
+        #[test]
+        fn fills_url_templates() {
+            let ymd = Ymd::parse("2026-02-04").unwrap();
+            assert_eq!(
+                ymd.fill_template("{yyyy}.{mm}/bview.{yyyy}{mm}{dd}.{hh}00.gz", 8),
+                "2026.02/bview.20260204.0800.gz"
+            );
+            assert_eq!(ymd.fill_template("rib.{ts}.bz2", 1), "rib.1770166800.bz2");
+        }
+    }
+}
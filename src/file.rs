@@ -9,3 +9,63 @@ pub fn ensure_dir(filename: &String) {
         fs::create_dir(path).unwrap();
     }
 }
+
+/// Derive the route collector name from an MRT filename,
+/// e.g. "./mrts/ris.rrc00.bview.20260131.0000.gz" -> "ris.rrc00"
+pub fn get_collector_name(filename: &str) -> String {
+    let basename = Path::new(filename)
+        .file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or(filename);
+
+    for separator in [".bview.", ".rib.", "_rib.", ".updates."] {
+        if let Some(pos) = basename.find(separator) {
+            return basename[..pos].to_owned();
+        }
+    }
+    basename.to_owned()
+}
+
+/// Derive the MRT source (project) from a route collector name,
+/// e.g. "ris.rrc00" -> "ris", "route-views.amsix" -> "route-views"
+pub fn get_source_name(collector: &str) -> String {
+    if collector.starts_with("ris") || collector.starts_with("rrc") {
+        String::from("ris")
+    } else if collector.starts_with("route-views") {
+        String::from("route-views")
+    } else if collector.starts_with("bgproutes") {
+        String::from("bgproutes.io")
+    } else if collector.starts_with("pch") {
+        String::from("pch")
+    } else {
+        String::from("other")
+    }
+}
+
+/// A file in the temp dir for tests to read from, which is removed when dropped
+#[cfg(test)]
+pub struct TempFile(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempFile {
+    pub fn new(name: &str, content: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "who-pays-whom-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, content).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
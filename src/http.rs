@@ -1,19 +1,51 @@
 pub mod http_client {
-    use log::{debug, info};
+    use crate::parse_errors::parse_error::ParseError;
+    use log::{debug, info, warn};
+    use regex::Regex;
+    use reqwest::StatusCode;
     use reqwest::blocking::Client;
-    use std::fs::File;
-    use std::io::Write;
-    use std::path::Path;
+    use reqwest::header::RANGE;
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{BufReader, BufWriter, Read, Write};
+    use std::path::{Path, PathBuf};
+    use std::thread;
     use std::time::Duration;
 
-    pub fn download_file(url: &str, dest: &Path) {
+    // Number of times a download is attempted before giving up
+    const DOWNLOAD_ATTEMPTS: u32 = 5;
+
+    // Seconds to wait after the first failed attempt, doubled after each subsequent failure
+    const BACKOFF_SECS: u64 = 2;
+
+    /// Download a URL to a local file, verifying its size and/or MD5 checksum when known.
+    ///
+    /// The body is streamed to "<dest>.part" and only renamed to dest once complete and
+    /// verified, so an existing dest is never a truncated download. A ".part" file left
+    /// behind by a killed run is resumed with an HTTP range request. Failed attempts are
+    /// retried with exponential backoff.
+    pub fn download_file(
+        url: &str,
+        dest: &Path,
+        size: Option<u64>,
+        md5: Option<&str>,
+    ) -> Result<(), ParseError> {
         if dest.exists() {
-            debug!(
-                "Not GETting URL {}, output file already exists {}",
-                url,
-                dest.to_str().unwrap(),
-            );
-            return;
+            match verify_file(dest, size, md5) {
+                Ok(()) => {
+                    debug!(
+                        "Not GETting URL {}, output file already exists {}",
+                        url,
+                        dest.to_str().unwrap(),
+                    );
+                    return Ok(());
+                }
+                Err(e) => {
+                    warn!("Re-downloading {}: {}", url, e);
+                    fs::remove_file(dest).map_err(|e| {
+                        ParseError::Download(format!("Failed to remove {:?}: {}", dest, e))
+                    })?;
+                }
+            }
         }
 
         info!("GET'ing URL {}", url);
@@ -21,25 +53,190 @@ pub mod http_client {
         let client = Client::builder()
             .timeout(Duration::from_secs(900)) // Increase default timeout
             .build()
-            .unwrap();
+            .map_err(|e| ParseError::Download(format!("Failed to build HTTP client: {}", e)))?;
+
+        let mut part = dest.as_os_str().to_owned();
+        part.push(".part");
+        let part = PathBuf::from(part);
+
+        let mut attempt = 1;
+        loop {
+            let result = download_part(&client, url, &part, size).and_then(|()| {
+                // A corrupt partial file can't be resumed, so start again from scratch
+                verify_file(&part, size, md5).inspect_err(|_| {
+                    let _ = fs::remove_file(&part);
+                })
+            });
+
+            match result {
+                Ok(()) => break,
+                Err(e) if attempt < DOWNLOAD_ATTEMPTS => {
+                    let backoff = BACKOFF_SECS * 2_u64.pow(attempt - 1);
+                    warn!(
+                        "Attempt {} of {} failed: {}, retrying in {}s",
+                        attempt, DOWNLOAD_ATTEMPTS, e, backoff
+                    );
+                    thread::sleep(Duration::from_secs(backoff));
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        fs::rename(&part, dest).map_err(|e| {
+            ParseError::Download(format!("Failed to rename {:?} to {:?}: {}", part, dest, e))
+        })?;
+
+        info!("Wrote to file {}", dest.to_str().unwrap());
+        Ok(())
+    }
+
+    /// Stream a URL into a partial file, resuming from the end of the file if it exists
+    fn download_part(
+        client: &Client,
+        url: &str,
+        part: &Path,
+        size: Option<u64>,
+    ) -> Result<(), ParseError> {
+        let offset = fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+        if offset > 0 && size == Some(offset) {
+            return Ok(());
+        }
+
+        let mut request = client.get(url);
+        if offset > 0 {
+            info!("Resuming {} from byte {}", url, offset);
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+
+        let response = request
+            .send()
+            .map_err(|e| ParseError::Download(format!("HTTP GET failed for URL {}: {}", url, e)))?;
+
+        // The partial file already holds the whole body
+        if offset > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            return Ok(());
+        }
+
+        let mut response = response
+            .error_for_status()
+            .map_err(|e| ParseError::Download(format!("HTTP GET failed for URL {}: {}", url, e)))?;
+
+        // Servers which don't support range requests send the whole body again
+        let resumed = response.status() == StatusCode::PARTIAL_CONTENT;
+        let file = if resumed {
+            OpenOptions::new().append(true).open(part)
+        } else {
+            File::create(part)
+        }
+        .map_err(|e| ParseError::Download(format!("Failed to open file {:?}: {}", part, e)))?;
+
+        let expected = response.content_length();
+        let mut writer = BufWriter::new(file);
+        let written = response
+            .copy_to(&mut writer)
+            .map_err(|e| ParseError::Download(format!("Failed to read response body when GETting {}. This could be due to a timeout. Error: {}", url, e)))?;
+        writer
+            .flush()
+            .map_err(|e| ParseError::Download(format!("Failed to write file {:?}: {}", part, e)))?;
+
+        if let Some(expected) = expected
+            && written != expected
+        {
+            return Err(ParseError::Download(format!(
+                "Only received {} of {} bytes from {}",
+                written, expected, url
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Check a file against its expected size and MD5 checksum, when known
+    pub fn verify_file(
+        path: &Path,
+        size: Option<u64>,
+        md5: Option<&str>,
+    ) -> Result<(), ParseError> {
+        if let Some(size) = size {
+            let actual = fs::metadata(path)
+                .map(|m| m.len())
+                .map_err(|e| ParseError::Verify(format!("Unable to stat {:?}: {}", path, e)))?;
+            if actual != size {
+                return Err(ParseError::Verify(format!(
+                    "{:?} is {} bytes, expected {}",
+                    path, actual, size
+                )));
+            }
+        }
+
+        if let Some(md5) = md5 {
+            let actual = md5_file(path)?;
+            if !actual.eq_ignore_ascii_case(md5) {
+                return Err(ParseError::Verify(format!(
+                    "{:?} has MD5 {}, expected {}",
+                    path, actual, md5
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn md5_file(path: &Path) -> Result<String, ParseError> {
+        let mut reader = File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| ParseError::Verify(format!("Unable to open {:?}: {}", path, e)))?;
+        let mut context = md5::Context::new();
+        let mut buf = [0_u8; 64 * 1024];
+        loop {
+            let len = reader
+                .read(&mut buf)
+                .map_err(|e| ParseError::Verify(format!("Unable to read {:?}: {}", path, e)))?;
+            if len == 0 {
+                break;
+            }
+            context.consume(&buf[..len]);
+        }
+        Ok(format!("{:x}", context.finalize()))
+    }
+
+    /// Return the body of a URL as text, for small files like index pages and checksums
+    pub fn get_text(url: &str) -> Result<String, ParseError> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .map_err(|e| {
+                ParseError::SourceListing(format!("Failed to build HTTP client: {}", e))
+            })?;
 
-        let response = client
+        client
             .get(url)
             .send()
-            .map_err(|e| format!("HTTP GET failed for URL {}: {}", url, e))
-            .unwrap();
+            .and_then(|r| r.error_for_status())
+            .and_then(|r| r.text())
+            .map_err(|e| {
+                ParseError::SourceListing(format!("HTTP GET failed for URL {}: {}", url, e))
+            })
+    }
 
-        let content = response
-            .bytes()
-            .map_err(|e| format!("Failed to read response bytes when GETting {}. This could be due to a timeout. Error: {}", url, e))
-            .unwrap();
+    /// Return the links in an HTTP directory index page, relative to the page URL.
+    /// Parent directory, sorting and absolute links are ignored.
+    pub fn get_dir_listing(url: &str) -> Result<Vec<String>, ParseError> {
+        debug!("Listing URL {}", url);
 
-        File::create(dest)
-            .map_err(|e| format!("Failed to create file {:?}: {}", dest, e))
-            .unwrap()
-            .write_all(&content)
-            .unwrap();
+        let body = get_text(url)?;
 
-        info!("Wrote to file {}", dest.to_str().unwrap());
+        let href = Regex::new(r#"(?i)href="([^"]+)""#).unwrap();
+        Ok(href
+            .captures_iter(&body)
+            .map(|c| c[1].to_owned())
+            .filter(|link| {
+                !link.starts_with('?')
+                    && !link.starts_with('/')
+                    && !link.starts_with("..")
+                    && !link.contains("://")
+            })
+            .collect())
     }
 }
@@ -0,0 +1,390 @@
+pub mod community_inference {
+    use crate::args::cli_args::LocationLevel;
+    use crate::file::ensure_dir;
+    use crate::mrt_asn::asn::MrtAsn;
+    use crate::parse_errors::parse_error::ParseError;
+    use crate::peer_attrs::peer_data::{PeerLocation, PeerType};
+    use crate::unmapped::unmapped_communities::UnmappedCommunities;
+    use itertools::Itertools;
+    use log::info;
+    use std::collections::{BTreeMap, HashMap};
+    use std::fmt::Write as _;
+    use std::fs;
+    use std::hash::Hash;
+
+    // Proposed mappings below this precision, or supported by fewer routes, are commented out
+    const MIN_PRECISION: f64 = 0.9;
+    const MIN_SUPPORT: f64 = 10.0;
+
+    /// How often a T1 ASN was seen to have each peer type and location with a neighbor,
+    /// on routes which its mapped communities classified
+    #[derive(Debug, Default)]
+    struct NeighborRelationship {
+        peer_types: HashMap<PeerType, u64>,
+        peer_locations: HashMap<PeerLocation, u64>,
+    }
+
+    /// The relationships of T1 ASNs with the ASN which follows them in the path,
+    /// as already known from mapped communities
+    #[derive(Debug, Default)]
+    pub struct KnownRelationships {
+        relationships: HashMap<(MrtAsn, MrtAsn), NeighborRelationship>,
+    }
+
+    impl KnownRelationships {
+        pub fn add_route(
+            &mut self,
+            asn: &MrtAsn,
+            next_asn: &MrtAsn,
+            peer_type: &PeerType,
+            peer_location: &PeerLocation,
+        ) {
+            let relationship = self
+                .relationships
+                .entry((asn.clone(), next_asn.clone()))
+                .or_default();
+            if *peer_type != PeerType::NoneFound {
+                *relationship
+                    .peer_types
+                    .entry(peer_type.clone())
+                    .or_insert(0) += 1;
+            }
+            if *peer_location != PeerLocation::NONE_FOUND {
+                *relationship
+                    .peer_locations
+                    .entry(peer_location.clone())
+                    .or_insert(0) += 1;
+            }
+        }
+
+        /// Merge the relationships seen in another file into this one
+        pub fn merge(&mut self, other: KnownRelationships) {
+            for (key, other_relationship) in other.relationships {
+                let relationship = self.relationships.entry(key).or_default();
+                for (peer_type, count) in other_relationship.peer_types {
+                    *relationship.peer_types.entry(peer_type).or_insert(0) += count;
+                }
+                for (peer_location, count) in other_relationship.peer_locations {
+                    *relationship
+                        .peer_locations
+                        .entry(peer_location)
+                        .or_insert(0) += count;
+                }
+            }
+        }
+
+        fn get(&self, asn: &MrtAsn, next_asn: &MrtAsn) -> Option<&NeighborRelationship> {
+            self.relationships.get(&(asn.clone(), next_asn.clone()))
+        }
+    }
+
+    /// AS relationships from a CAIDA style "as-rel" file, used for neighbors which
+    /// the mapped communities don't give a peer type for
+    #[derive(Debug, Default)]
+    pub struct AsRelationships {
+        // The peer type of the second ASN, as seen by the first
+        peer_types: HashMap<(u32, u32), PeerType>,
+    }
+
+    impl AsRelationships {
+        /// Read lines of the form "<provider>|<customer>|-1" or "<peer>|<peer>|0",
+        /// any further fields are ignored. Lines starting with # are comments.
+        pub fn from_file(filename: &str) -> Result<Self, ParseError> {
+            let invalid =
+                |e: String| ParseError::InvalidAsRelationships(format!("{}: {}", filename, e));
+            let mut as_relationships = Self::default();
+            for line in oneio::read_lines(filename).map_err(|e| invalid(e.to_string()))? {
+                let line = line.map_err(|e| invalid(e.to_string()))?;
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                let fields: Vec<&str> = line.split('|').collect();
+                let (Some(a), Some(b), Some(rel)) = (fields.first(), fields.get(1), fields.get(2))
+                else {
+                    return Err(invalid(format!("too few fields in {}", line)));
+                };
+                let a = a.parse::<u32>().map_err(|_| invalid(line.to_owned()))?;
+                let b = b.parse::<u32>().map_err(|_| invalid(line.to_owned()))?;
+                let (a_sees_b, b_sees_a) = match *rel {
+                    "-1" => (PeerType::Customer, PeerType::Upstream),
+                    "0" => (PeerType::Peer, PeerType::Peer),
+                    _ => return Err(invalid(line.to_owned())),
+                };
+                as_relationships.peer_types.insert((a, b), a_sees_b);
+                as_relationships.peer_types.insert((b, a), b_sees_a);
+            }
+            info!(
+                "Read {} AS relationships from {}",
+                as_relationships.peer_types.len() / 2,
+                filename
+            );
+            Ok(as_relationships)
+        }
+
+        fn get(&self, asn: &MrtAsn, next_asn: &MrtAsn) -> Option<&PeerType> {
+            self.peer_types
+                .get(&(asn.clone().to_u32(), next_asn.clone().to_u32()))
+        }
+    }
+
+    /// Votes for the meaning of a community, each route shares its vote between the
+    /// labels known for its next ASN, in proportion to how often each was seen
+    #[derive(Debug)]
+    struct Votes<T> {
+        votes: HashMap<T, f64>,
+        // Routes whose next ASN had a known label
+        support: f64,
+    }
+
+    impl<T: Clone + Eq + Hash> Votes<T> {
+        fn new() -> Self {
+            Self {
+                votes: HashMap::new(),
+                support: 0.0,
+            }
+        }
+
+        fn add(&mut self, routes: u64, labels: &HashMap<T, u64>) {
+            let total: u64 = labels.values().sum();
+            if total == 0 {
+                return;
+            }
+            self.support += routes as f64;
+            for (label, count) in labels {
+                *self.votes.entry(label.clone()).or_insert(0.0) +=
+                    routes as f64 * *count as f64 / total as f64;
+            }
+        }
+
+        /// The label with the most votes, and the share of the votes it has
+        fn get_best(&self) -> Option<(&T, f64)> {
+            self.votes
+                .iter()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(label, votes)| (label, votes / self.support))
+        }
+    }
+
+    /// The proposed mappings of a single community
+    struct Proposal {
+        value: u16,
+        routes: u64,
+        peer_type: Votes<PeerType>,
+        peer_location: Votes<PeerLocation>,
+    }
+
+    /// Draft mappings for unmapped communities, inferred from the relationship of the
+    /// tagging ASN with the ASN which follows it on the routes carrying them
+    pub struct DraftMappings {
+        proposals: BTreeMap<u32, Vec<Proposal>>,
+    }
+
+    impl DraftMappings {
+        pub fn infer(
+            unmapped: &UnmappedCommunities,
+            known: &KnownRelationships,
+            as_relationships: Option<&AsRelationships>,
+            level: LocationLevel,
+        ) -> Self {
+            let mut proposals = BTreeMap::<u32, Vec<Proposal>>::new();
+            for (asn, value, community) in unmapped.iter() {
+                let mut peer_type = Votes::<PeerType>::new();
+                let mut peer_location = Votes::<PeerLocation>::new();
+                for (next_asn, routes) in community.get_next_asns() {
+                    // Routes the tagging ASN originated say nothing about a neighbor
+                    let Some(next_asn) = next_asn else {
+                        continue;
+                    };
+                    let relationship = known.get(asn, next_asn);
+                    match relationship.filter(|r| !r.peer_types.is_empty()) {
+                        Some(relationship) => peer_type.add(*routes, &relationship.peer_types),
+                        None => {
+                            if let Some(t) = as_relationships.and_then(|r| r.get(asn, next_asn)) {
+                                peer_type.add(*routes, &HashMap::from([(t.clone(), 1)]));
+                            }
+                        }
+                    }
+                    if let Some(relationship) = relationship {
+                        let mut locations = HashMap::<PeerLocation, u64>::new();
+                        for (location, count) in &relationship.peer_locations {
+                            *locations.entry(location.roll_up(level)).or_insert(0) += count;
+                        }
+                        peer_location.add(*routes, &locations);
+                    }
+                }
+
+                proposals
+                    .entry(asn.clone().to_u32())
+                    .or_default()
+                    .push(Proposal {
+                        value,
+                        routes: community.get_routes(),
+                        peer_type,
+                        peer_location,
+                    });
+            }
+
+            for asn_proposals in proposals.values_mut() {
+                asn_proposals.sort_by_key(|p| (std::cmp::Reverse(p.routes), p.value));
+            }
+            Self { proposals }
+        }
+
+        /// Describe the votes for a community, and propose a mapping line for comm_data.rs,
+        /// which is commented out if it isn't precise or supported enough
+        fn write_votes<T: Clone + Eq + Hash>(
+            out: &mut String,
+            name: &str,
+            votes: &Votes<T>,
+            routes: u64,
+            mapping: impl Fn(&T) -> String,
+            label: impl Fn(&T) -> String,
+        ) {
+            let Some((best, precision)) = votes.get_best() else {
+                writeln!(out, "// No known {} for the next ASN on any route", name).unwrap();
+                return;
+            };
+            let others = votes
+                .votes
+                .iter()
+                .filter(|(l, _)| *l != best)
+                .sorted_by(|(_, a), (_, b)| b.total_cmp(a))
+                .map(|(l, v)| format!("{} {:.0}%", label(l), 100.0 * v / votes.support))
+                .join(", ");
+            writeln!(
+                out,
+                "// {}: {} {:.0}% of {:.0} routes with a known {} ({:.0}% of all routes){}",
+                name,
+                label(best),
+                100.0 * precision,
+                votes.support,
+                name,
+                100.0 * votes.support / routes as f64,
+                if others.is_empty() {
+                    String::new()
+                } else {
+                    format!(", then {}", others)
+                }
+            )
+            .unwrap();
+            let comment = if precision >= MIN_PRECISION && votes.support >= MIN_SUPPORT {
+                ""
+            } else {
+                "// "
+            };
+            writeln!(out, "{}{}", comment, mapping(best)).unwrap();
+        }
+
+        /// Write the proposals as lines which can be reviewed and copied into comm_data.rs
+        pub fn to_file(&self, filename: &String) {
+            let mut out = String::from(
+                "// Draft community mappings, inferred from the relationship of each T1 with the\n\
+                 // next ASN in the AS paths of routes carrying unmapped communities. These need\n\
+                 // checking against the operator's documentation before adding to comm_data.rs.\n",
+            );
+            writeln!(
+                out,
+                "// Mappings below {:.0}% precision, or supported by fewer than {} routes, are commented out.",
+                100.0 * MIN_PRECISION,
+                MIN_SUPPORT
+            )
+            .unwrap();
+
+            for (asn, proposals) in &self.proposals {
+                writeln!(out, "\n/* AS{} */", asn).unwrap();
+                for proposal in proposals {
+                    let community = format!("{}:{}", asn, proposal.value);
+                    writeln!(out, "\n// {} seen on {} routes", community, proposal.routes).unwrap();
+                    Self::write_votes(
+                        &mut out,
+                        "peer type",
+                        &proposal.peer_type,
+                        proposal.routes,
+                        |t| {
+                            format!(
+                                "cm.add_peer_type(matcher(\"{}\"), PeerType::{:?});",
+                                community, t
+                            )
+                        },
+                        |t| format!("{:?}", t),
+                    );
+                    Self::write_votes(
+                        &mut out,
+                        "location",
+                        &proposal.peer_location,
+                        proposal.routes,
+                        |l| {
+                            format!(
+                                "cm.add_peer_location(matcher(\"{}\"), location(\"{}\"));",
+                                community, l
+                            )
+                        },
+                        |l| l.to_string(),
+                    );
+                }
+            }
+
+            ensure_dir(filename);
+            fs::write(filename, out).unwrap();
+            info!("Wrote draft mappings to {}", filename);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::file::TempFile;
+
+        /// Write an as-rel file to a temporary file and read it back
+        fn read_as_relationships(name: &str, content: &str) -> Result<AsRelationships, ParseError> {
+            let file = TempFile::new(name, content);
+            AsRelationships::from_file(file.path())
+        }
+
+        #[test]
+        fn reads_both_directions_of_each_relationship() {
+            let as_relationships = read_as_relationships(
+                "as-rel.txt",
+                "# source:topology|BGP\n\
+                 \n\
+                 3356|64500|-1\n\
+                 174|3356|0|bgp\n",
+            )
+            .unwrap();
+            let get = |a: u32, b: u32| {
+                as_relationships
+                    .get(&MrtAsn::from_u32(a), &MrtAsn::from_u32(b))
+                    .cloned()
+            };
+            assert_eq!(get(3356, 64500), Some(PeerType::Customer));
+            assert_eq!(get(64500, 3356), Some(PeerType::Upstream));
+            assert_eq!(get(174, 3356), Some(PeerType::Peer));
+            assert_eq!(get(3356, 174), Some(PeerType::Peer));
+            assert_eq!(get(174, 64500), None);
+        }
+
+        #[test]
+        fn rejects_invalid_lines() {
+            for line in ["3356|64500", "3356|AS64500|-1", "3356|64500|1"] {
+                assert!(
+                    matches!(
+                        read_as_relationships("invalid.txt", line),
+                        Err(ParseError::InvalidAsRelationships(_))
+                    ),
+                    "{}",
+                    line
+                );
+            }
+        }
+
+        #[test]
+        fn missing_file_is_an_error() {
+            assert!(matches!(
+                AsRelationships::from_file("/nonexistent/as-rel.txt"),
+                Err(ParseError::InvalidAsRelationships(_))
+            ));
+        }
+    }
+}
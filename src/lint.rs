@@ -0,0 +1,619 @@
+pub mod mapping_lint {
+    use crate::comm_mappings::community_mappings::{AsnMappings, CommMappings};
+    use crate::comm_matchers::community_matchers::{CommunityMatcher, CommunityMatchers};
+    use crate::dates::ymd::Ymd;
+    use crate::mrt_asn::asn::MrtAsn;
+    use crate::mrt_communities::standard_communities::StandardCommunity;
+    use log::{error, info, warn};
+    use std::collections::HashMap;
+    use std::fmt;
+
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum Severity {
+        /// Probably intended, but worth a look
+        Warning,
+        /// Almost certainly a mistake, fails the lint
+        Error,
+    }
+
+    /// A problem found in the mappings of an ASN
+    #[derive(Debug)]
+    pub struct Finding {
+        severity: Severity,
+        asn: MrtAsn,
+        // When the version of the ASN's mappings is valid, e.g. "from 2024-06-01", empty if there is only one
+        version: String,
+        message: String,
+    }
+
+    impl fmt::Display for Finding {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "AS{}", self.asn)?;
+            if !self.version.is_empty() {
+                write!(f, " ({})", self.version)?;
+            }
+            write!(f, ": {}", self.message)
+        }
+    }
+
+    // The values a matcher matches, as sorted inclusive ranges
+    type ValueRanges = Vec<(u16, u16)>;
+
+    /// The entries of one kind of mapping (e.g. peer type) in a version of an ASN's mappings
+    struct Entries<'a, T> {
+        kind: &'static str,
+        describe: fn(&T) -> String,
+        matchers: &'a CommunityMatchers<T>,
+        entries: Vec<(CommunityMatcher, &'a T, ValueRanges)>,
+    }
+
+    impl<'a, T: Clone + PartialEq> Entries<'a, T> {
+        fn new(
+            kind: &'static str,
+            describe: fn(&T) -> String,
+            matchers: &'a CommunityMatchers<T>,
+        ) -> Self {
+            let mut entries: Vec<(CommunityMatcher, &T, ValueRanges)> = matchers
+                .get_entries()
+                .into_iter()
+                .map(|(matcher, value)| {
+                    let ranges = matcher.get_value_ranges();
+                    (matcher, value, ranges)
+                })
+                .collect();
+            // Keep findings in a stable order between runs
+            entries
+                .sort_by_key(|(matcher, _, ranges)| (matcher.get_asn(), ranges.first().copied()));
+            Self {
+                kind,
+                describe,
+                matchers,
+                entries,
+            }
+        }
+
+        /// Communities mapped under this ASN with another network's ASN field
+        fn check_foreign(&self, lint: &mut Lint) {
+            for (matcher, _, _) in &self.entries {
+                let other = MrtAsn::from_u32(matcher.get_asn());
+                if other == *lint.asn {
+                    continue;
+                }
+                if other.is_t1_asn() || lint.asn_mappings.has_asn(&other) {
+                    lint.add(
+                        Severity::Error,
+                        format!(
+                            "{} {} has the ASN field of AS{}, which has its own mappings",
+                            self.kind, matcher, other
+                        ),
+                    );
+                } else if matcher.get_asn() != 0 {
+                    // ASN 0 can't belong to another network, operators such as Verizon use it
+                    lint.add(
+                        Severity::Warning,
+                        format!("{} {} has the ASN field of AS{}", self.kind, matcher, other),
+                    );
+                }
+            }
+        }
+
+        /// Entries which match some of the same communities. With the same meaning they're
+        /// redundant, where one is nested in the other it overrides it, otherwise which one
+        /// wins depends only on where the ranges start, so they contradict each other.
+        fn check_overlaps(&self, lint: &mut Lint) {
+            for (i, (matcher_a, value_a, ranges_a)) in self.entries.iter().enumerate() {
+                for (matcher_b, value_b, ranges_b) in &self.entries[i + 1..] {
+                    if matcher_a.get_asn() != matcher_b.get_asn() || !overlaps(ranges_a, ranges_b) {
+                        continue;
+                    }
+                    if value_a == value_b {
+                        lint.add(
+                            Severity::Warning,
+                            format!(
+                                "{} {} and {} overlap, both mapped to {}",
+                                self.kind,
+                                matcher_a,
+                                matcher_b,
+                                (self.describe)(value_a)
+                            ),
+                        );
+                    } else if contains(ranges_a, ranges_b) || contains(ranges_b, ranges_a) {
+                        lint.add(
+                            Severity::Warning,
+                            format!(
+                                "{} {} ({}) and {} ({}) are nested with different meanings",
+                                self.kind,
+                                matcher_a,
+                                (self.describe)(value_a),
+                                matcher_b,
+                                (self.describe)(value_b)
+                            ),
+                        );
+                    } else {
+                        lint.add(
+                            Severity::Error,
+                            format!(
+                                "{} {} ({}) and {} ({}) overlap with different meanings",
+                                self.kind,
+                                matcher_a,
+                                (self.describe)(value_a),
+                                matcher_b,
+                                (self.describe)(value_b)
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+
+        /// Entries which never win a lookup, because other entries match all their communities first
+        fn check_unreachable(&self, lint: &mut Lint) {
+            for (matcher, value, ranges) in &self.entries {
+                let reachable = ranges.iter().any(|(start, end)| {
+                    (*start..=*end).any(|v| {
+                        let community = StandardCommunity::new(matcher.get_asn(), v);
+                        self.matchers
+                            .get(&community)
+                            .is_some_and(|found| std::ptr::eq(found, *value))
+                    })
+                });
+                if !reachable {
+                    lint.add(
+                        Severity::Error,
+                        format!(
+                            "{} {} ({}) is unreachable, other entries match all of its communities",
+                            self.kind,
+                            matcher,
+                            (self.describe)(value)
+                        ),
+                    );
+                }
+            }
+        }
+
+        fn check(&self, lint: &mut Lint) {
+            self.check_foreign(lint);
+            self.check_overlaps(lint);
+            self.check_unreachable(lint);
+        }
+    }
+
+    /// Whether two sorted lists of inclusive ranges have any value in common
+    fn overlaps(a: &[(u16, u16)], b: &[(u16, u16)]) -> bool {
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i].1 < b[j].0 {
+                i += 1;
+            } else if b[j].1 < a[i].0 {
+                j += 1;
+            } else {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Whether every value in the sorted ranges b is also in the sorted ranges a
+    fn contains(a: &[(u16, u16)], b: &[(u16, u16)]) -> bool {
+        b.iter().all(|(start, end)| {
+            a.iter()
+                .any(|(a_start, a_end)| a_start <= start && end <= a_end)
+        })
+    }
+
+    /// Findings for the version of an ASN's mappings being checked
+    struct Lint<'a> {
+        asn_mappings: &'a AsnMappings,
+        asn: &'a MrtAsn,
+        version: String,
+        findings: Vec<Finding>,
+    }
+
+    impl Lint<'_> {
+        fn add(&mut self, severity: Severity, message: String) {
+            self.findings.push(Finding {
+                severity,
+                asn: self.asn.clone(),
+                version: self.version.clone(),
+                message,
+            });
+        }
+
+        fn check_mappings(&mut self, mappings: &CommMappings) {
+            let peer_types = Entries::new(
+                "peer type",
+                |t| format!("{:?}", t),
+                mappings.get_peer_type_matchers(),
+            );
+            let peer_locations = Entries::new(
+                "location",
+                |l| l.to_string(),
+                mappings.get_peer_location_matchers(),
+            );
+            peer_types.check(self);
+            peer_locations.check(self);
+            Entries::new(
+                "session type",
+                |t| format!("{:?}", t),
+                mappings.get_session_type_matchers(),
+            )
+            .check(self);
+
+            let neighbors = Entries::new(
+                "neighbor",
+                |asn| format!("AS{}", asn),
+                mappings.get_neighbor_matchers(),
+            );
+            neighbors.check(self);
+            for (matcher, neighbor, _) in &neighbors.entries {
+                if *neighbor == self.asn {
+                    self.add(
+                        Severity::Error,
+                        format!("neighbor {} is mapped to the ASN itself", matcher),
+                    );
+                }
+            }
+
+            // Coverage gaps
+            if peer_types.entries.is_empty() && !peer_locations.entries.is_empty() {
+                self.add(
+                    Severity::Error,
+                    String::from(
+                        "has location mappings but no peer type mappings, its routes can't be classified",
+                    ),
+                );
+            } else if !peer_types.entries.is_empty() && peer_locations.entries.is_empty() {
+                self.add(
+                    Severity::Warning,
+                    String::from(
+                        "has peer type mappings but no location mappings, its peerings have no location",
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Describe when a version of an ASN's mappings is valid
+    fn format_version(valid_from: Option<u64>, valid_until: Option<u64>) -> String {
+        let format = |ts: u64| Ymd::from_unix_ts(ts).0.to_string();
+        match (valid_from, valid_until) {
+            (None, None) => String::from("at all times"),
+            (Some(from), None) => format!("from {}", format(from)),
+            (None, Some(until)) => format!("until {}", format(until)),
+            (Some(from), Some(until)) => format!("from {} until {}", format(from), format(until)),
+        }
+    }
+
+    /// Check the mappings for mistakes: communities with another network's ASN field,
+    /// overlapping or unreachable entries, versions which are never used, and gaps in coverage
+    pub fn lint_mappings(asn_mappings: &AsnMappings) -> Vec<Finding> {
+        let mut versions =
+            HashMap::<&MrtAsn, Vec<(Option<u64>, Option<u64>, &CommMappings)>>::new();
+        for (asn, valid_from, valid_until, mappings) in asn_mappings.get_versions() {
+            versions
+                .entry(asn)
+                .or_default()
+                .push((valid_from, valid_until, mappings));
+        }
+
+        let mut findings = Vec::<Finding>::new();
+        for asn in MrtAsn::get_t1_asns() {
+            if !versions.contains_key(&asn) {
+                findings.push(Finding {
+                    severity: Severity::Warning,
+                    asn,
+                    version: String::new(),
+                    message: String::from("is a T1 but has no mappings"),
+                });
+            }
+        }
+
+        let mut asns: Vec<&MrtAsn> = versions.keys().copied().collect();
+        asns.sort_by_key(|asn| (*asn).clone().to_u32());
+        for asn in asns {
+            let asn_versions = &versions[asn];
+            for (i, (valid_from, valid_until, mappings)) in asn_versions.iter().enumerate() {
+                let version = if asn_versions.len() > 1 {
+                    format_version(*valid_from, *valid_until)
+                } else {
+                    String::new()
+                };
+                let mut lint = Lint {
+                    asn_mappings,
+                    asn,
+                    version,
+                    findings: Vec::new(),
+                };
+
+                if valid_from
+                    .zip(*valid_until)
+                    .is_some_and(|(from, until)| from >= until)
+                {
+                    lint.add(
+                        Severity::Error,
+                        String::from("is never valid, it ends before it starts"),
+                    );
+                }
+
+                // Where versions overlap the one which became valid most recently is used, or
+                // the one inserted last if they became valid at the same time, e.g. when an
+                // ASN's mappings are accidentally inserted twice
+                let shadowed_by = asn_versions
+                    .iter()
+                    .enumerate()
+                    .find(|(j, (from, until, _))| {
+                        *j > i
+                            && from == valid_from
+                            && until.is_none_or(|until| valid_until.is_some_and(|v| v <= until))
+                    });
+                if let Some((_, (from, until, _))) = shadowed_by {
+                    lint.add(
+                        Severity::Error,
+                        format!(
+                            "is never used, the version valid {} is used instead",
+                            format_version(*from, *until)
+                        ),
+                    );
+                }
+
+                lint.check_mappings(mappings);
+                findings.extend(lint.findings);
+            }
+        }
+        findings
+    }
+
+    /// Log the findings and a summary, returning false if the lint failed
+    pub fn log_findings(findings: &[Finding], deny_warnings: bool) -> bool {
+        let mut errors = 0;
+        let mut warnings = 0;
+        for finding in findings {
+            match finding.severity {
+                Severity::Error => {
+                    errors += 1;
+                    error!("{}", finding);
+                }
+                Severity::Warning => {
+                    warnings += 1;
+                    warn!("{}", finding);
+                }
+            }
+        }
+        info!(
+            "Linted community mappings: {} errors, {} warnings",
+            errors, warnings
+        );
+        errors == 0 && (!deny_warnings || warnings == 0)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::peer_attrs::peer_data::{Continent, PeerType};
+
+        /// Mappings for AS174 with a location for every community, so that only the
+        /// findings for the peer type entries under test are reported
+        fn peer_types(entries: &[(&str, PeerType)]) -> CommMappings {
+            let mut mappings = CommMappings::new(
+                HashMap::new(),
+                HashMap::from([(StandardCommunity::new(174, 1), Continent::Europe)]),
+            );
+            for (matcher, peer_type) in entries {
+                mappings.add_peer_type(matcher.parse().unwrap(), peer_type.clone());
+            }
+            mappings
+        }
+
+        /// The findings for AS174, which is a T1
+        fn lint(asn_mappings: &AsnMappings) -> Vec<(Severity, String)> {
+            lint_mappings(asn_mappings)
+                .into_iter()
+                .filter(|finding| finding.asn == MrtAsn::from_u32(174))
+                .map(|finding| (finding.severity, finding.to_string()))
+                .collect()
+        }
+
+        fn lint_peer_types(entries: &[(&str, PeerType)]) -> Vec<(Severity, String)> {
+            lint(&AsnMappings::new(HashMap::from([(
+                MrtAsn::from_u32(174),
+                peer_types(entries),
+            )])))
+        }
+
+        #[test]
+        fn clean_mappings_have_no_findings() {
+            assert_eq!(
+                lint_peer_types(&[
+                    ("174:100-199", PeerType::Peer),
+                    ("174:200-299", PeerType::Customer)
+                ]),
+                []
+            );
+        }
+
+        #[test]
+        fn overlapping_entries_with_different_meanings_are_errors() {
+            assert_eq!(
+                lint_peer_types(&[
+                    ("174:100-199", PeerType::Peer),
+                    ("174:150-249", PeerType::Customer)
+                ]),
+                [(
+                    Severity::Error,
+                    String::from(
+                        "AS174: peer type 174:100-199 (Peer) and 174:150-249 (Customer) overlap with different meanings"
+                    )
+                )]
+            );
+        }
+
+        #[test]
+        fn nested_and_redundant_entries_are_warnings() {
+            assert_eq!(
+                lint_peer_types(&[
+                    ("174:1xx", PeerType::Peer),
+                    ("174:150-159", PeerType::Customer)
+                ]),
+                [(
+                    Severity::Warning,
+                    String::from(
+                        "AS174: peer type 174:100-199 (Peer) and 174:150-159 (Customer) are nested with different meanings"
+                    )
+                )]
+            );
+            assert_eq!(
+                lint_peer_types(&[
+                    ("174:100-199", PeerType::Peer),
+                    ("174:150-249", PeerType::Peer)
+                ]),
+                [(
+                    Severity::Warning,
+                    String::from(
+                        "AS174: peer type 174:100-199 and 174:150-249 overlap, both mapped to Peer"
+                    )
+                )]
+            );
+        }
+
+        #[test]
+        fn entries_which_never_win_a_lookup_are_unreachable() {
+            let findings =
+                lint_peer_types(&[("174:5-5", PeerType::Customer), ("174:5", PeerType::Peer)]);
+            assert!(findings.contains(&(
+                Severity::Error,
+                String::from(
+                    "AS174: peer type 174:5-5 (Customer) is unreachable, other entries match all of its communities"
+                )
+            )));
+        }
+
+        #[test]
+        fn communities_with_another_asn_field_are_flagged() {
+            assert_eq!(
+                lint_peer_types(&[
+                    ("3356:1", PeerType::Peer),
+                    ("64500:1", PeerType::Peer),
+                    ("0:1", PeerType::Peer)
+                ]),
+                [
+                    (
+                        Severity::Error,
+                        String::from(
+                            "AS174: peer type 3356:1 has the ASN field of AS3356, which has its own mappings"
+                        )
+                    ),
+                    (
+                        Severity::Warning,
+                        String::from("AS174: peer type 64500:1 has the ASN field of AS64500")
+                    )
+                ]
+            );
+        }
+
+        #[test]
+        fn reports_gaps_in_coverage() {
+            let no_peer_types = CommMappings::new(
+                HashMap::new(),
+                HashMap::from([(StandardCommunity::new(174, 1), Continent::Europe)]),
+            );
+            let no_locations = CommMappings::new(
+                HashMap::from([(StandardCommunity::new(174, 1), PeerType::Peer)]),
+                HashMap::new(),
+            );
+            assert_eq!(
+                lint(&AsnMappings::new(HashMap::from([(
+                    MrtAsn::from_u32(174),
+                    no_peer_types
+                )]))),
+                [(
+                    Severity::Error,
+                    String::from(
+                        "AS174: has location mappings but no peer type mappings, its routes can't be classified"
+                    )
+                )]
+            );
+            assert_eq!(
+                lint(&AsnMappings::new(HashMap::from([(
+                    MrtAsn::from_u32(174),
+                    no_locations
+                )]))),
+                [(
+                    Severity::Warning,
+                    String::from(
+                        "AS174: has peer type mappings but no location mappings, its peerings have no location"
+                    )
+                )]
+            );
+        }
+
+        #[test]
+        fn reports_neighbors_mapped_to_the_asn_itself() {
+            let mut mappings = peer_types(&[("174:100", PeerType::Peer)]);
+            mappings.add_neighbor("174:200".parse().unwrap(), MrtAsn::from_u32(174));
+            assert_eq!(
+                lint(&AsnMappings::new(HashMap::from([(
+                    MrtAsn::from_u32(174),
+                    mappings
+                )]))),
+                [(
+                    Severity::Error,
+                    String::from("AS174: neighbor 174:200 is mapped to the ASN itself")
+                )]
+            );
+        }
+
+        #[test]
+        fn reports_versions_which_are_never_used() {
+            let date = |date| Some(Ymd::parse(date).unwrap());
+            let asn = MrtAsn::from_u32(174);
+            let mut asn_mappings = AsnMappings::new(HashMap::new());
+            asn_mappings.insert_between(
+                asn.clone(),
+                date("2025-01-01"),
+                date("2025-06-01"),
+                peer_types(&[("174:100", PeerType::Peer)]),
+            );
+            asn_mappings.insert_between(
+                asn.clone(),
+                date("2025-01-01"),
+                None,
+                peer_types(&[("174:100", PeerType::Customer)]),
+            );
+            asn_mappings.insert_between(
+                asn,
+                date("2026-01-01"),
+                date("2025-01-01"),
+                peer_types(&[("174:100", PeerType::Peer)]),
+            );
+            assert_eq!(
+                lint(&asn_mappings),
+                [
+                    (
+                        Severity::Error,
+                        String::from(
+                            "AS174 (from 2025-01-01 until 2025-06-01): is never used, the version valid from 2025-01-01 is used instead"
+                        )
+                    ),
+                    (
+                        Severity::Error,
+                        String::from(
+                            "AS174 (from 2026-01-01 until 2025-01-01): is never valid, it ends before it starts"
+                        )
+                    )
+                ]
+            );
+        }
+
+        #[test]
+        fn t1s_without_mappings_are_warnings() {
+            let findings = lint(&AsnMappings::new(HashMap::new()));
+            assert_eq!(
+                findings,
+                [(
+                    Severity::Warning,
+                    String::from("AS174: is a T1 but has no mappings")
+                )]
+            );
+        }
+    }
+}
@@ -1,7 +1,11 @@
+use indicatif::MultiProgress;
+use indicatif_log_bridge::LogWrapper;
 use std::io::Write;
 
-pub fn setup_logging(level: &str) {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(level))
+/// Setup logging, returning the progress bars which log lines are printed above,
+/// so that progress bars aren't broken up by log lines
+pub fn setup_logging(level: &str) -> MultiProgress {
+    let logger = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(level))
         .format(|buf, record| {
             let ts = buf.timestamp_micros();
             writeln!(
@@ -13,5 +17,13 @@ pub fn setup_logging(level: &str) {
                 record.args()
             )
         })
-        .init();
+        .build();
+    let max_level = logger.filter();
+
+    let progress = MultiProgress::new();
+    LogWrapper::new(progress.clone(), logger)
+        .try_init()
+        .unwrap();
+    log::set_max_level(max_level);
+    progress
 }
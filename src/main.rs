@@ -1,63 +1,126 @@
 pub mod args;
 pub mod comm_data;
 pub mod comm_mappings;
+pub mod comm_matchers;
+pub mod coverage;
+pub mod dates;
 pub mod file;
 pub mod http;
+pub mod inference;
+pub mod lint;
 pub mod logging;
 pub mod mrt_asn;
 pub mod mrt_communities;
 pub mod mrt_peer;
 pub mod mrt_route;
+pub mod neighbors;
+pub mod parse_errors;
 pub mod parse_mrt;
+pub mod parse_results;
 pub mod parse_threaded;
 pub mod peer_attrs;
 pub mod peerings;
+pub mod progress;
+pub mod rib_sources;
 pub mod ribs;
+pub mod rpsl;
+pub mod run_stats;
+pub mod scheduler;
+pub mod snapshots;
 pub mod triple_paths;
+pub mod unmapped;
+pub mod vantage_points;
 
+use crate::comm_mappings::community_mappings::AsnMappings;
+use crate::dates::ymd::Ymd;
+use crate::lint::mapping_lint::{lint_mappings, log_findings};
 use crate::parse_threaded::threaded_parser::init_parallel_parsing;
-use crate::ribs::rib_getter::download_ribs_for_day;
+use crate::rib_sources::rib_source::{self, write_manifest};
+use crate::ribs::rib_getter::{RibFilter, download_ribs, get_ribs_for_day, log_dry_run};
+use crate::rpsl::rpsl_import::RpslCandidates;
 use crate::{args::cli_args::RibsSource, ribs::rib_getter::RibFile};
+use log::error;
 use rayon::ThreadPoolBuilder;
 
 fn main() {
     let args = args::cli_args::parse_cli_arg();
-    if args.debug {
-        logging::setup_logging("debug");
+    let progress = if args.debug {
+        logging::setup_logging("debug")
     } else {
-        logging::setup_logging("info");
-    }
+        logging::setup_logging("info")
+    };
 
     ThreadPoolBuilder::new()
         .num_threads((args.threads).try_into().unwrap())
         .build_global()
         .unwrap();
 
-    match args.ribs_source {
-        // Download MRT files and then parse them - one file per thread
+    match &args.ribs_source {
+        // Download MRT files (or stream them) and then parse them
         RibsSource::Download(_) => {
-            let rib_files = download_ribs_for_day(args.get_ribs_ymd(), args.get_ribs_path());
-            init_parallel_parsing(&rib_files, &args)
+            let download_args = args.get_download_args();
+            let rib_files = Ymd::parse(args.get_ribs_ymd())
+                .and_then(|date| {
+                    let source = rib_source::from_args(download_args)?;
+                    let filter = RibFilter::from_args(download_args);
+                    get_ribs_for_day(
+                        source.as_ref(),
+                        &filter,
+                        &date,
+                        &download_args.get_hours(),
+                        args.get_ribs_path(),
+                    )
+                })
+                .unwrap_or_else(|e| {
+                    error!("{}", e);
+                    std::process::exit(1);
+                });
+
+            if let Some(manifest) = &download_args.write_manifest {
+                write_manifest(&rib_files, manifest);
+            }
+
+            if download_args.dry_run {
+                log_dry_run(&rib_files);
+                return;
+            }
+
+            let rib_files = if args.stream() {
+                rib_files
+            } else {
+                download_ribs(rib_files, args.strict)
+            };
+            init_parallel_parsing(&rib_files, &args, &progress)
+        }
+
+        // Check the community mappings, exiting non-zero if there are errors, e.g. for CI
+        RibsSource::LintMappings(lint_args) => {
+            let findings = lint_mappings(&AsnMappings::default());
+            if !log_findings(&findings, lint_args.deny_warnings) {
+                std::process::exit(1);
+            }
         }
 
-        // Parse a single existing file - split across multiple threads
-        RibsSource::File(_) => {
-            let rib_files = Vec::from([RibFile::new(String::new(), args.get_rib_file().clone())]);
-            init_parallel_parsing(&rib_files, &args)
+        // Suggest community mappings from an aut-num's remarks in a local RPSL dump
+        RibsSource::ImportRpsl(import_args) => {
+            match RpslCandidates::from_file(&import_args.dump_file, import_args.aut_num) {
+                Ok(candidates) => candidates.to_file(&import_args.output),
+                Err(e) => {
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
+            }
         }
 
-        // Parse multiple existing files - one file per thread
-        RibsSource::Files(_) => {
+        // Parse existing files - split across threads as set by --strategy
+        RibsSource::File(_) | RibsSource::Files(_) => {
             let rib_files: Vec<RibFile> = args
                 .get_rib_files()
                 .iter()
-                .map(|filename| RibFile {
-                    url: String::new(),
-                    filename: filename.clone(),
-                })
+                .map(|filename| RibFile::new(String::new(), filename.clone()))
                 .collect();
 
-            init_parallel_parsing(&rib_files, &args);
+            init_parallel_parsing(&rib_files, &args, &progress);
         }
     };
 }
@@ -56,6 +56,10 @@ pub mod asn {
             SKIP_ASNS.contains(&(self.0.to_u32(), filename))
         }
 
+        pub fn get_t1_asns() -> Vec<Self> {
+            TIER1_ASNS.iter().map(|asn| Self::from_u32(*asn)).collect()
+        }
+
         pub fn is_t1_asn(&self) -> bool {
             TIER1_ASNS.contains(&self.0.to_u32())
         }
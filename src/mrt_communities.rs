@@ -1,7 +1,7 @@
 pub mod standard_communities {
     use crate::comm_mappings::community_mappings::AsnMappings;
     use crate::mrt_asn::asn::MrtAsn;
-    use crate::peer_attrs::peer_data::{PeerLocation, PeerType};
+    use crate::peer_attrs::peer_data::{PeerLocation, PeerType, SessionType};
     use bgpkit_parser::models::Community;
     use log::debug;
     use serde::ser::{SerializeSeq, SerializeTuple};
@@ -46,7 +46,7 @@ pub mod standard_communities {
             &self.asn
         }
 
-        fn get_value(&self) -> &u16 {
+        pub fn get_value(&self) -> &u16 {
             &self.value
         }
     }
@@ -83,55 +83,114 @@ pub mod standard_communities {
         pub fn from_vec(communities: Vec<Community>) -> Self {
             let mut standard_communities = Self::new(Vec::<StandardCommunity>::new());
             for community in communities {
+                // We're not interested in NO_EXPORT, NO_ADVERTISE etc., other
+                // well-known communities such as BLACKHOLE are decoded as custom communities
                 if let Community::Custom(asn, value) = community {
                     standard_communities.add(StandardCommunity::new(asn.to_u32(), value));
-                } else if let Community::NoAdvertise
-                | Community::NoExport
-                | Community::NoExportSubConfed = community
-                {
-                    // NO-OP - we're not interested in these communities
-                } else {
-                    panic!(
-                        "Couldn't unpack Community into StandardCommunity: {}",
-                        community
-                    );
                 }
             }
             standard_communities
         }
 
+        /// The communities which have this ASN as the prefix
+        pub fn with_asn(&'a self, asn: &'a MrtAsn) -> impl Iterator<Item = &'a StandardCommunity> {
+            self.standard_communities
+                .iter()
+                .filter(move |standard_community| standard_community.get_asn() == asn)
+        }
+
+        /// Check if any community has this ASN as the prefix
+        pub fn has_asn(&self, asn: &MrtAsn) -> bool {
+            self.standard_communities
+                .iter()
+                .any(|standard_community| standard_community.get_asn() == asn)
+        }
+
+        /// Find the first community which the local ASN has a mapping for. Communities with
+        /// the local ASN in their ASN field are checked first, then communities the local ASN
+        /// owns despite having a different ASN field (e.g. Verizon's 0:201). These aren't used
+        /// if another network on the AS path could have set them instead, because their ASN
+        /// field is that network, or that network also has a mapping for them.
+        /// Returns the community and its mapping.
+        fn find_mapping<T>(
+            &'a self,
+            local_asn: &MrtAsn,
+            as_path: &[MrtAsn],
+            asn_mappings: &'a AsnMappings,
+            timestamp: u32,
+            get_mapping: impl Fn(&StandardCommunity) -> Option<&'a T>,
+        ) -> Option<(&'a StandardCommunity, &'a T)> {
+            let (own, foreign): (Vec<&StandardCommunity>, Vec<&StandardCommunity>) = self
+                .standard_communities
+                .iter()
+                .partition(|standard_community| standard_community.get_asn() == local_asn);
+
+            if let Some(mapping) = own.into_iter().find_map(|standard_community| {
+                Some((standard_community, get_mapping(standard_community)?))
+            }) {
+                return Some(mapping);
+            }
+
+            foreign.into_iter().find_map(|standard_community| {
+                let mapping = get_mapping(standard_community)?;
+                let ambiguous = as_path.iter().any(|asn| {
+                    asn != local_asn
+                        && (asn == standard_community.get_asn()
+                            || asn_mappings.has_mapping(asn, standard_community, timestamp))
+                });
+                if ambiguous {
+                    debug!(
+                        "Not attributing {:?} to ASN {:?}, another network on {:?} may have set it",
+                        standard_community, local_asn, as_path
+                    );
+                    return None;
+                }
+                Some((standard_community, mapping))
+            })
+        }
+
         pub fn get_peer_location(
             &'a self,
             local_asn: &MrtAsn,
+            as_path: &[MrtAsn],
             asn_mappings: &'a AsnMappings,
+            timestamp: u32,
         ) -> &'a PeerLocation {
-            for standard_community in &self.standard_communities {
-                if standard_community.get_asn() == local_asn
-                    && let Some(peer_location) =
-                        asn_mappings.get_asn_peer_location(local_asn, standard_community)
-                {
-                    return peer_location;
-                }
+            if let Some((_, peer_location)) = self.find_mapping(
+                local_asn,
+                as_path,
+                asn_mappings,
+                timestamp,
+                |standard_community| {
+                    asn_mappings.get_asn_peer_location(local_asn, standard_community, timestamp)
+                },
+            ) {
+                return peer_location;
             }
             debug!(
                 "Couldn't get peer location for ASN {:?} from: {:#?}",
                 local_asn, self
             );
-            &PeerLocation::NoneFound
+            &PeerLocation::NONE_FOUND
         }
 
         pub fn get_peer_type(
             &'a self,
             local_asn: &MrtAsn,
+            as_path: &[MrtAsn],
             asn_mappings: &'a AsnMappings,
+            timestamp: u32,
         ) -> &'a PeerType {
-            for standard_community in &self.standard_communities {
-                if standard_community.get_asn() == local_asn
-                    && let Some(peer_type) =
-                        asn_mappings.get_asn_peer_type(local_asn, standard_community)
-                {
-                    return peer_type;
-                }
+            if let Some((_, peer_type)) = self.find_mapping(
+                local_asn,
+                as_path,
+                asn_mappings,
+                timestamp,
+                |standard_community| {
+                    asn_mappings.get_asn_peer_type(local_asn, standard_community, timestamp)
+                },
+            ) {
+                return peer_type;
             }
             debug!(
                 "Couldn't get peer type for ASN {:?} from: {:#?}",
@@ -139,5 +198,149 @@ pub mod standard_communities {
             );
             &PeerType::NoneFound
         }
+
+        pub fn get_session_type(
+            &'a self,
+            local_asn: &MrtAsn,
+            as_path: &[MrtAsn],
+            asn_mappings: &'a AsnMappings,
+            timestamp: u32,
+        ) -> &'a SessionType {
+            if let Some((_, session_type)) = self.find_mapping(
+                local_asn,
+                as_path,
+                asn_mappings,
+                timestamp,
+                |standard_community| {
+                    asn_mappings.get_asn_session_type(local_asn, standard_community, timestamp)
+                },
+            ) {
+                return session_type;
+            }
+            &SessionType::NoneFound
+        }
+
+        /// Find a community which says which neighbor the local ASN learned the route from
+        pub fn get_neighbor(
+            &'a self,
+            local_asn: &MrtAsn,
+            as_path: &[MrtAsn],
+            asn_mappings: &'a AsnMappings,
+            timestamp: u32,
+        ) -> Option<(&'a StandardCommunity, &'a MrtAsn)> {
+            self.find_mapping(
+                local_asn,
+                as_path,
+                asn_mappings,
+                timestamp,
+                |standard_community| {
+                    asn_mappings.get_asn_neighbor(local_asn, standard_community, timestamp)
+                },
+            )
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::comm_mappings::community_mappings::CommMappings;
+        use std::collections::HashMap;
+
+        fn asns(asns: &[u32]) -> Vec<MrtAsn> {
+            asns.iter().map(|asn| MrtAsn::from_u32(*asn)).collect()
+        }
+
+        fn communities(communities: &[(u32, u16)]) -> StandardCommunities {
+            StandardCommunities::new(
+                communities
+                    .iter()
+                    .map(|(asn, value)| StandardCommunity::new(*asn, *value))
+                    .collect(),
+            )
+        }
+
+        /// AS701 owns 0:201 despite its ASN field, AS3356 has its own 3356:2
+        fn asn_mappings() -> AsnMappings {
+            let peer_types = |mappings: &[(u32, u16, PeerType)]| {
+                CommMappings::new(
+                    mappings
+                        .iter()
+                        .map(|(asn, value, peer_type)| {
+                            (StandardCommunity::new(*asn, *value), peer_type.clone())
+                        })
+                        .collect(),
+                    HashMap::new(),
+                )
+            };
+            AsnMappings::new(HashMap::from([
+                (
+                    MrtAsn::from_u32(701),
+                    peer_types(&[(0, 201, PeerType::Customer), (701, 1, PeerType::Peer)]),
+                ),
+                (
+                    MrtAsn::from_u32(3356),
+                    peer_types(&[(3356, 2, PeerType::Peer), (0, 666, PeerType::Upstream)]),
+                ),
+                (
+                    MrtAsn::from_u32(1299),
+                    peer_types(&[(0, 666, PeerType::Customer), (3356, 2, PeerType::Customer)]),
+                ),
+            ]))
+        }
+
+        /// The peer type of the local ASN, from the communities on a route with the AS path
+        fn peer_type(communities: &[(u32, u16)], local_asn: u32, as_path: &[u32]) -> PeerType {
+            self::communities(communities)
+                .get_peer_type(
+                    &MrtAsn::from_u32(local_asn),
+                    &asns(as_path),
+                    &asn_mappings(),
+                    0,
+                )
+                .clone()
+        }
+
+        #[test]
+        fn prefers_communities_with_the_local_asn_field() {
+            assert_eq!(
+                peer_type(&[(0, 201), (701, 1)], 701, &[64500, 701, 65000]),
+                PeerType::Peer
+            );
+        }
+
+        #[test]
+        fn attributes_foreign_communities_to_the_network_which_owns_them() {
+            assert_eq!(
+                peer_type(&[(0, 201)], 701, &[64500, 701, 65000]),
+                PeerType::Customer
+            );
+        }
+
+        #[test]
+        fn ignores_foreign_communities_another_network_on_the_path_could_have_set() {
+            // AS3356's own ASN field is on the path
+            assert_eq!(
+                peer_type(&[(3356, 2)], 1299, &[1299, 3356]),
+                PeerType::NoneFound
+            );
+            assert_eq!(
+                peer_type(&[(3356, 2)], 1299, &[1299, 65000]),
+                PeerType::Customer
+            );
+
+            // Both AS1299 and AS3356 map 0:666, so it can't be attributed to either
+            assert_eq!(
+                peer_type(&[(0, 666)], 1299, &[1299, 3356]),
+                PeerType::NoneFound
+            );
+            assert_eq!(
+                peer_type(&[(0, 666)], 3356, &[1299, 3356]),
+                PeerType::NoneFound
+            );
+            assert_eq!(
+                peer_type(&[(0, 666)], 3356, &[3356, 65000]),
+                PeerType::Upstream
+            );
+        }
     }
 }
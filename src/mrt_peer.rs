@@ -2,6 +2,7 @@ pub mod peer {
     use bgpkit_parser::models::Peer as BgpKit_Peer;
     use serde::{Serialize, Serializer, ser::SerializeStruct};
     use std::collections::HashMap;
+    use std::net::IpAddr;
 
     #[derive(Clone, Debug)]
     pub struct Peer(BgpKit_Peer);
@@ -23,6 +24,14 @@ pub mod peer {
         pub fn new(peer: BgpKit_Peer) -> Self {
             Self(peer)
         }
+
+        pub fn get_asn(&self) -> u32 {
+            self.0.peer_asn.to_u32()
+        }
+
+        pub fn get_ip(&self) -> IpAddr {
+            self.0.peer_ip
+        }
     }
 
     #[derive(Debug)]
@@ -43,8 +52,8 @@ pub mod peer {
             Self::new(pt)
         }
 
-        pub fn get_peer(&self, id: &u16) -> &Peer {
-            self.peer_table.get(id).unwrap()
+        pub fn get_peer(&self, id: &u16) -> Option<&Peer> {
+            self.peer_table.get(id)
         }
     }
 }
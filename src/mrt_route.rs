@@ -2,7 +2,7 @@ pub mod route {
     use crate::mrt_asn::asn::MrtAsn;
     use crate::mrt_communities::standard_communities::StandardCommunities;
     use crate::mrt_peer::peer::Peer;
-    use crate::peer_attrs::peer_data::{PeerLocation, PeerType};
+    use crate::peer_attrs::peer_data::{PeerLocation, PeerType, SessionType};
     use ipnet::IpNet;
     use serde::ser::SerializeStruct as _;
     use serde::{Serialize, Serializer};
@@ -22,6 +22,7 @@ pub mod route {
         peer_as: MrtAsn,
         peer_type: PeerType,
         peer_location: PeerLocation,
+        session_type: SessionType,
         as_path: Vec<MrtAsn>,
         filename: String,
         next_hop: IpAddr,
@@ -35,11 +36,12 @@ pub mod route {
         where
             S: Serializer,
         {
-            let mut state = serializer.serialize_struct("Route", 11)?;
+            let mut state = serializer.serialize_struct("Route", 12)?;
             state.serialize_field("local_as", &self.local_as)?;
             state.serialize_field("peer_as", &self.peer_as)?;
             state.serialize_field("peer_type", &self.peer_type)?;
             state.serialize_field("peer_location", &self.peer_location)?;
+            state.serialize_field("session_type", &self.session_type)?;
             state.serialize_field("as_path", &self.as_path)?;
             state.serialize_field("filename", &self.filename)?;
             state.serialize_field("next_hop", &self.next_hop)?;
@@ -51,11 +53,13 @@ pub mod route {
     }
 
     impl Route {
+        #[allow(clippy::too_many_arguments)]
         pub fn new(
             local_as: MrtAsn,
             peer_as: MrtAsn,
             peer_type: PeerType,
             peer_location: PeerLocation,
+            session_type: SessionType,
             as_path: Vec<MrtAsn>,
             filename: String,
             next_hop: IpAddr,
@@ -68,6 +72,7 @@ pub mod route {
                 peer_as,
                 peer_type,
                 peer_location,
+                session_type,
                 as_path,
                 filename,
                 next_hop,
@@ -101,6 +106,14 @@ pub mod route {
             &self.peer_type
         }
 
+        pub fn get_session_type(&self) -> &SessionType {
+            &self.session_type
+        }
+
+        pub fn get_peer(&self) -> &Peer {
+            &self.peer
+        }
+
         pub fn get_prefix(&self) -> &IpNet {
             &self.prefix
         }
@@ -0,0 +1,233 @@
+pub mod neighbor_checks {
+    use crate::file::ensure_dir;
+    use crate::mrt_asn::asn::MrtAsn;
+    use crate::mrt_communities::standard_communities::StandardCommunity;
+    use ipnet::IpNet;
+    use log::{info, warn};
+    use serde::Serialize;
+    use std::collections::{BTreeMap, HashMap};
+    use std::fs::File;
+    use std::io::BufWriter;
+
+    // Number of example prefixes kept for each mismatched community
+    const MAX_EXAMPLES: usize = 5;
+
+    /// Whether the AS paths of routes carrying a per-neighbor community agree with it
+    #[derive(Debug, Eq, PartialEq, Serialize)]
+    pub enum Verdict {
+        // The next ASN in the path was always the claimed neighbor
+        Ok,
+        // The next ASN was usually the claimed neighbor, the other paths are unusual
+        PathAnomaly,
+        // The next ASN was more often not the claimed neighbor, the mapping is probably wrong
+        MappingError,
+    }
+
+    /// How often the next ASN after the tagging ASN matched a per-neighbor community
+    #[derive(Debug, Serialize)]
+    pub struct NeighborCheck {
+        neighbor: MrtAsn,
+        matches: u64,
+        mismatches: u64,
+        // The next ASNs seen instead of the neighbor, "origin" if the tagging ASN originated the route
+        actual_neighbors: HashMap<String, u64>,
+        examples: Vec<String>,
+    }
+
+    impl NeighborCheck {
+        fn new(neighbor: MrtAsn) -> Self {
+            Self {
+                neighbor,
+                matches: 0,
+                mismatches: 0,
+                actual_neighbors: HashMap::new(),
+                examples: Vec::new(),
+            }
+        }
+
+        fn add(&mut self, next_asn: Option<&MrtAsn>, prefix: &IpNet) {
+            if next_asn == Some(&self.neighbor) {
+                self.matches += 1;
+                return;
+            }
+            self.mismatches += 1;
+            let actual = next_asn.map_or(String::from("origin"), |asn| format!("AS{}", asn));
+            *self.actual_neighbors.entry(actual).or_insert(0) += 1;
+            if self.examples.len() < MAX_EXAMPLES {
+                self.examples.push(prefix.to_string());
+            }
+        }
+
+        fn merge(&mut self, other: NeighborCheck) {
+            self.matches += other.matches;
+            self.mismatches += other.mismatches;
+            for (actual, count) in other.actual_neighbors {
+                *self.actual_neighbors.entry(actual).or_insert(0) += count;
+            }
+            let space = MAX_EXAMPLES.saturating_sub(self.examples.len());
+            self.examples.extend(other.examples.into_iter().take(space));
+        }
+
+        fn get_verdict(&self) -> Verdict {
+            if self.mismatches == 0 {
+                Verdict::Ok
+            } else if self.mismatches > self.matches {
+                Verdict::MappingError
+            } else {
+                Verdict::PathAnomaly
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct NeighborCheckReport<'a> {
+        verdict: Verdict,
+        #[serde(flatten)]
+        check: &'a NeighborCheck,
+    }
+
+    /// Cross-checks per-neighbor communities, e.g. "learned from AS1299", against
+    /// the ASN which actually follows the tagging ASN in each AS path
+    #[derive(Debug, Default)]
+    pub struct NeighborChecks {
+        checks: HashMap<(MrtAsn, StandardCommunity), NeighborCheck>,
+    }
+
+    impl NeighborChecks {
+        pub fn add_route(
+            &mut self,
+            asn: &MrtAsn,
+            community: &StandardCommunity,
+            neighbor: &MrtAsn,
+            next_asn: Option<&MrtAsn>,
+            prefix: &IpNet,
+        ) {
+            self.checks
+                .entry((asn.clone(), community.clone()))
+                .or_insert_with(|| NeighborCheck::new(neighbor.clone()))
+                .add(next_asn, prefix);
+        }
+
+        /// Merge the checks made in another file into this one
+        pub fn merge(&mut self, other: NeighborChecks) {
+            for (key, other_check) in other.checks {
+                match self.checks.get_mut(&key) {
+                    Some(check) => check.merge(other_check),
+                    None => {
+                        self.checks.insert(key, other_check);
+                    }
+                }
+            }
+        }
+
+        /// Checks keyed by the tagging ASN, then the community
+        fn get_report(&self) -> BTreeMap<String, BTreeMap<String, NeighborCheckReport<'_>>> {
+            let mut report = BTreeMap::<String, BTreeMap<String, NeighborCheckReport>>::new();
+            for ((asn, community), check) in &self.checks {
+                report.entry(format!("AS{}", asn)).or_default().insert(
+                    format!("{}:{}", community.get_asn(), community.get_value()),
+                    NeighborCheckReport {
+                        verdict: check.get_verdict(),
+                        check,
+                    },
+                );
+            }
+            report
+        }
+
+        /// Log the per-neighbor communities which don't match the AS paths they're seen on
+        pub fn log_summary(&self) {
+            for ((asn, community), check) in &self.checks {
+                match check.get_verdict() {
+                    Verdict::Ok => {}
+                    Verdict::PathAnomaly => info!(
+                        "AS{} community {}:{} claims neighbor AS{}, but {} of {} paths have another next ASN",
+                        asn,
+                        community.get_asn(),
+                        community.get_value(),
+                        check.neighbor,
+                        check.mismatches,
+                        check.mismatches + check.matches
+                    ),
+                    Verdict::MappingError => warn!(
+                        "AS{} community {}:{} is mapped to neighbor AS{}, but {} of {} paths have another next ASN, check the mapping",
+                        asn,
+                        community.get_asn(),
+                        community.get_value(),
+                        check.neighbor,
+                        check.mismatches,
+                        check.mismatches + check.matches
+                    ),
+                }
+            }
+        }
+
+        pub fn to_file(&self, filename: &String) {
+            ensure_dir(filename);
+            let writer = BufWriter::new(File::create(filename).unwrap());
+            serde_json::to_writer_pretty(writer, &self.get_report()).unwrap();
+            info!("Wrote JSON to {}", filename);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Check AS1273's "learned from AS1299" community against routes with each next ASN
+        fn check(next_asns: &[Option<u32>]) -> NeighborChecks {
+            let mut checks = NeighborChecks::default();
+            let prefix: IpNet = "192.0.2.0/24".parse().unwrap();
+            for next_asn in next_asns {
+                checks.add_route(
+                    &MrtAsn::from_u32(1273),
+                    &StandardCommunity::new(1273, 39970),
+                    &MrtAsn::from_u32(1299),
+                    next_asn.map(MrtAsn::from_u32).as_ref(),
+                    &prefix,
+                );
+            }
+            checks
+        }
+
+        fn get_check(checks: &NeighborChecks) -> &NeighborCheck {
+            &checks.checks[&(MrtAsn::from_u32(1273), StandardCommunity::new(1273, 39970))]
+        }
+
+        #[test]
+        fn matching_paths_are_ok() {
+            let checks = check(&[Some(1299), Some(1299)]);
+            let check = get_check(&checks);
+            assert_eq!((check.matches, check.mismatches), (2, 0));
+            assert_eq!(check.get_verdict(), Verdict::Ok);
+        }
+
+        #[test]
+        fn occasional_mismatches_are_path_anomalies() {
+            let checks = check(&[Some(1299), Some(1299), Some(3356), None]);
+            let check = get_check(&checks);
+            assert_eq!((check.matches, check.mismatches), (2, 2));
+            assert_eq!(check.get_verdict(), Verdict::PathAnomaly);
+            assert_eq!(
+                check.actual_neighbors,
+                HashMap::from([(String::from("AS3356"), 1), (String::from("origin"), 1)])
+            );
+            assert_eq!(check.examples.len(), 2);
+        }
+
+        #[test]
+        fn mostly_mismatching_paths_are_mapping_errors() {
+            let checks = check(&[Some(1299), Some(3356), Some(3356)]);
+            assert_eq!(get_check(&checks).get_verdict(), Verdict::MappingError);
+        }
+
+        #[test]
+        fn merges_checks_from_other_files() {
+            let mut checks = check(&[Some(1299)]);
+            checks.merge(check(&[Some(3356), Some(3356)]));
+            let check = get_check(&checks);
+            assert_eq!((check.matches, check.mismatches), (1, 2));
+            assert_eq!(check.get_verdict(), Verdict::MappingError);
+        }
+    }
+}
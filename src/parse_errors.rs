@@ -0,0 +1,187 @@
+pub mod parse_error {
+    use ipnet::IpNet;
+    use log::{info, warn};
+    use serde::Serialize;
+    use std::collections::HashMap;
+    use std::fmt;
+
+    /// Problems which can be encountered when fetching or parsing an MRT file
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub enum ParseError {
+        BrokerQuery(String),
+        SourceListing(String),
+        InvalidDate(String),
+        InvalidCommunity(String),
+        InvalidLocation(String),
+        InvalidAsRelationships(String),
+        Rpsl(String),
+        Download(String),
+        Verify(String),
+        OpenFile(String),
+        ReadFile(String),
+        InvalidRecord(String),
+        MissingFirstRecord,
+        MissingPeerTable,
+        NotRibAfi,
+        UnexpectedRibType(String),
+        UnknownPeerIndex(u16),
+        MissingNextHop(IpNet),
+        NonIpv6MpNlri(IpNet),
+        MissingAsPath(IpNet),
+    }
+
+    impl fmt::Display for ParseError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Self::BrokerQuery(e) => write!(f, "BGPKIT broker query failed: {}", e),
+                Self::SourceListing(e) => write!(f, "Unable to list MRT source: {}", e),
+                Self::InvalidDate(d) => write!(f, "Invalid date {}, expected yyyy-mm-dd", d),
+                Self::InvalidCommunity(c) => write!(f, "Invalid community matcher {}", c),
+                Self::InvalidLocation(l) => write!(f, "Invalid peer location {}", l),
+                Self::InvalidAsRelationships(e) => write!(f, "Invalid AS relationships {}", e),
+                Self::Rpsl(e) => write!(f, "Unable to import RPSL: {}", e),
+                Self::Download(e) => write!(f, "Download failed: {}", e),
+                Self::Verify(e) => write!(f, "Download verification failed: {}", e),
+                Self::OpenFile(e) => write!(f, "Unable to open MRT file: {}", e),
+                Self::ReadFile(e) => write!(f, "Unable to read MRT file: {}", e),
+                Self::InvalidRecord(e) => write!(f, "Unable to parse MRT record: {}", e),
+                Self::MissingFirstRecord => write!(f, "Unable to extract first record"),
+                Self::MissingPeerTable => write!(f, "Couldn't extract peer table from table dump"),
+                Self::NotRibAfi => write!(f, "MRT record isn't of type RibAfi"),
+                Self::UnexpectedRibType(t) => write!(f, "Unexpected RIB record type {}", t),
+                Self::UnknownPeerIndex(i) => write!(f, "Unknown peer index {}", i),
+                Self::MissingNextHop(p) => write!(f, "No next-hop for {}", p),
+                Self::NonIpv6MpNlri(p) => write!(f, "MP NLRI is used for non-IPv6 info for {}", p),
+                Self::MissingAsPath(p) => write!(f, "Unable to unpack AS Path for {}", p),
+            }
+        }
+    }
+
+    impl std::error::Error for ParseError {}
+
+    impl ParseError {
+        /// A short name for the type of error, used as a key when counting errors
+        pub fn kind(&self) -> &'static str {
+            match self {
+                Self::BrokerQuery(_) => "BrokerQuery",
+                Self::SourceListing(_) => "SourceListing",
+                Self::InvalidDate(_) => "InvalidDate",
+                Self::InvalidCommunity(_) => "InvalidCommunity",
+                Self::InvalidLocation(_) => "InvalidLocation",
+                Self::InvalidAsRelationships(_) => "InvalidAsRelationships",
+                Self::Rpsl(_) => "Rpsl",
+                Self::Download(_) => "Download",
+                Self::Verify(_) => "Verify",
+                Self::OpenFile(_) => "OpenFile",
+                Self::ReadFile(_) => "ReadFile",
+                Self::InvalidRecord(_) => "InvalidRecord",
+                Self::MissingFirstRecord => "MissingFirstRecord",
+                Self::MissingPeerTable => "MissingPeerTable",
+                Self::NotRibAfi => "NotRibAfi",
+                Self::UnexpectedRibType(_) => "UnexpectedRibType",
+                Self::UnknownPeerIndex(_) => "UnknownPeerIndex",
+                Self::MissingNextHop(_) => "MissingNextHop",
+                Self::NonIpv6MpNlri(_) => "NonIpv6MpNlri",
+                Self::MissingAsPath(_) => "MissingAsPath",
+            }
+        }
+    }
+
+    /// Count of errors seen in a single file, keyed by error kind
+    #[derive(Debug, Default, Serialize)]
+    pub struct FileErrors {
+        counts: HashMap<&'static str, u64>,
+        first_error: Option<String>,
+    }
+
+    impl FileErrors {
+        pub fn add_error(&mut self, error: &ParseError) {
+            *self.counts.entry(error.kind()).or_insert(0) += 1;
+            if self.first_error.is_none() {
+                self.first_error = Some(error.to_string());
+            }
+        }
+
+        pub fn total(&self) -> u64 {
+            self.counts.values().sum()
+        }
+    }
+
+    /// Errors for all files, keyed by filename
+    #[derive(Debug, Default, Serialize)]
+    pub struct ParseErrors {
+        file_errors: HashMap<String, FileErrors>,
+    }
+
+    impl ParseErrors {
+        pub fn add_error(&mut self, filename: &str, error: &ParseError) {
+            self.file_errors
+                .entry(filename.to_owned())
+                .or_default()
+                .add_error(error);
+        }
+
+        pub fn get_file_errors(&self, filename: &str) -> Option<&FileErrors> {
+            self.file_errors.get(filename)
+        }
+
+        pub fn total(&self) -> u64 {
+            self.file_errors.values().map(|e| e.total()).sum()
+        }
+
+        /// Log a summary of all skipped records, per file
+        pub fn log_summary(&self) {
+            if self.file_errors.is_empty() {
+                info!("No errors encountered");
+                return;
+            }
+
+            warn!(
+                "Skipped {} bad records across {} files",
+                self.total(),
+                self.file_errors.len()
+            );
+            for (filename, file_errors) in &self.file_errors {
+                warn!(
+                    "{}: {} errors {:?}, first error: {}",
+                    filename,
+                    file_errors.total(),
+                    file_errors.counts,
+                    file_errors.first_error.as_deref().unwrap_or_default(),
+                );
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn counts_errors_per_file_and_kind() {
+            let mut parse_errors = ParseErrors::default();
+            parse_errors.add_error("a.gz", &ParseError::UnknownPeerIndex(7));
+            parse_errors.add_error("a.gz", &ParseError::UnknownPeerIndex(8));
+            parse_errors.add_error("a.gz", &ParseError::NotRibAfi);
+            parse_errors.add_error("b.gz", &ParseError::MissingFirstRecord);
+
+            let a = parse_errors.get_file_errors("a.gz").unwrap();
+            assert_eq!(a.total(), 3);
+            assert_eq!(a.counts["UnknownPeerIndex"], 2);
+            assert_eq!(a.counts["NotRibAfi"], 1);
+            assert_eq!(parse_errors.total(), 4);
+            assert!(parse_errors.get_file_errors("c.gz").is_none());
+        }
+
+        #[test]
+        fn keeps_the_first_error_of_each_file() {
+            let mut file_errors = FileErrors::default();
+            file_errors.add_error(&ParseError::UnknownPeerIndex(7));
+            file_errors.add_error(&ParseError::NotRibAfi);
+            assert_eq!(
+                file_errors.first_error.as_deref(),
+                Some("Unknown peer index 7")
+            );
+        }
+    }
+}
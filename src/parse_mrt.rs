@@ -4,88 +4,185 @@ pub mod mrt_parser {
     use crate::mrt_communities::standard_communities::StandardCommunities;
     use crate::mrt_peer::peer::PeerTable;
     use crate::mrt_route::route::Route;
-    use crate::peer_attrs::peer_data::PeerType;
-    use crate::peerings::peering_data::PeeringData;
-    use crate::triple_paths::triple_t1_paths::TripleT1Paths;
+    use crate::parse_errors::parse_error::{ParseError, ParseErrors};
+    use crate::parse_results::parse_result::ParseResults;
+    use crate::peer_attrs::peer_data::{PeerLocation, PeerType};
+    use bgpkit_parser::MrtRecord;
     use bgpkit_parser::models::{
         AsPathSegment, AttrFlags, AttrType, Attribute, AttributeValue, MrtMessage, RibAfiEntries,
         RibEntry, TableDumpV2Message, TableDumpV2Type,
     };
-    use bgpkit_parser::{BgpkitParser, MrtRecord};
     use ipnet::IpNet;
+    use log::debug;
     use std::net::IpAddr;
     use std::path::Path;
     use std::sync::{Arc, RwLock};
 
+    /// How a T1's communities classify its relationship with the next ASN in the path
+    struct Classification<'a> {
+        peer_type: &'a PeerType,
+        peer_location: &'a PeerLocation,
+    }
+
     // Shared data that needs to be passed around when parsing an MRT entry
     pub struct MrtData<'a> {
         mrt_entry: &'a MrtRecord,
-        global_peerings: &'a Arc<RwLock<PeeringData>>,
-        triple_t1_paths: &'a Arc<RwLock<TripleT1Paths>>,
         peer_id_map: &'a PeerTable,
         asn_mappings: &'a AsnMappings,
         fp: &'a String,
+        parse_errors: &'a Arc<RwLock<ParseErrors>>,
+        results: &'a mut ParseResults,
+        collector: &'a str,
+        strict: bool,
     }
 
     impl<'a> MrtData<'a> {
+        #[allow(clippy::too_many_arguments)]
         pub fn new(
             mrt_entry: &'a MrtRecord,
-            global_peerings: &'a Arc<RwLock<PeeringData>>,
-            triple_t1_paths: &'a Arc<RwLock<TripleT1Paths>>,
             peer_id_map: &'a PeerTable,
             asn_mappings: &'a AsnMappings,
             fp: &'a String,
+            parse_errors: &'a Arc<RwLock<ParseErrors>>,
+            results: &'a mut ParseResults,
+            collector: &'a str,
+            strict: bool,
         ) -> Self {
             Self {
                 mrt_entry,
-                global_peerings,
-                triple_t1_paths,
                 peer_id_map,
                 asn_mappings,
                 fp,
+                parse_errors,
+                results,
+                collector,
+                strict,
             }
         }
+
+        /// Abort in strict mode, otherwise count the error against this file and carry on
+        fn handle_error(&self, error: ParseError) {
+            handle_error(self.fp, error, self.parse_errors, self.strict);
+        }
     }
 
-    /// Extract the prefix from each RIB entry and proceed to check the AS path for that prefix
-    pub fn parse_mrt_entry(mrt_data: MrtData) {
-        let rib_entries = get_rib_entries(mrt_data.mrt_entry, mrt_data.fp);
-        if rib_entries.is_none() {
-            return;
+    /// Abort in strict mode, otherwise count the error against the file and carry on
+    pub fn handle_error(
+        fp: &str,
+        error: ParseError,
+        parse_errors: &Arc<RwLock<ParseErrors>>,
+        strict: bool,
+    ) {
+        if strict {
+            panic!("{} in file {}", error, fp);
         }
-        let rib_entries = rib_entries.unwrap_or_else(|| {
-            panic!(
-                "Unable to consume RIB entries from {}: {:#?}",
-                mrt_data.fp, mrt_data.mrt_entry
-            )
-        });
+        debug!("Skipping bad record: {} in file {}", error, fp);
+        parse_errors.write().unwrap().add_error(fp, &error);
+    }
+
+    /// Extract the prefix from each RIB entry and proceed to check the AS path for that prefix
+    pub fn parse_mrt_entry(mut mrt_data: MrtData) {
+        mrt_data.results.file_stats.add_record();
+        let rib_entries = match get_rib_entries(mrt_data.mrt_entry) {
+            Ok(Some(rib_entries)) => rib_entries,
+            Ok(None) => {
+                mrt_data.results.file_stats.add_skipped_default_route();
+                return;
+            }
+            Err(e) => {
+                mrt_data.handle_error(e);
+                return;
+            }
+        };
 
         let prefix = rib_entries.prefix.prefix;
 
         for rib_entry in &rib_entries.rib_entries {
-            check_as_seq(prefix, rib_entry, &mrt_data);
+            mrt_data.results.file_stats.add_rib_entry();
+            check_as_seq(prefix, rib_entry, &mut mrt_data);
         }
     }
 
     /// Check an AS Path if it containers two neighboring T1 ASNs
-    pub fn check_as_seq(prefix: IpNet, rib_entry: &RibEntry, mrt_data: &MrtData) {
-        let mut as_sequence = get_as_sequence(rib_entry, mrt_data.fp);
+    pub fn check_as_seq(prefix: IpNet, rib_entry: &RibEntry, mrt_data: &mut MrtData) {
+        let mut as_sequence = match get_as_sequence(rib_entry, &prefix) {
+            Ok(as_sequence) => as_sequence,
+            Err(e) => {
+                mrt_data.handle_error(e);
+                return;
+            }
+        };
         as_sequence.dedup();
 
         if as_sequence.is_empty() {
             // Some collectors include iBGP paths or self originated prefixes with no AS path
+            mrt_data.results.file_stats.add_skipped_empty_path();
             return;
         }
 
+        // Route collectors which are known to contain inaccurate data after certain ASNs
+        let basename = Path::new(mrt_data.fp)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(mrt_data.fp);
+        let has_skip_asn = as_sequence.iter().any(|asn| asn.is_skip_asn(basename));
+
+        if !as_sequence.iter().any(|asn| asn.is_t1_asn()) {
+            if has_skip_asn {
+                mrt_data.results.file_stats.add_skipped_skip_asn();
+            }
+            return;
+        }
+
+        // Classify each T1 in the path once, for both its peerings and the community checks.
+        // Use the mappings in force when the RIB was dumped.
+        let communities = get_communities(rib_entry);
+        let timestamp = mrt_data.mrt_entry.common_header.timestamp;
+        let asn_mappings = mrt_data.asn_mappings;
+        let classifications: Vec<Option<Classification>> = as_sequence
+            .iter()
+            .map(|asn| {
+                asn.is_t1_asn().then(|| Classification {
+                    peer_type: communities.get_peer_type(
+                        asn,
+                        &as_sequence,
+                        asn_mappings,
+                        timestamp,
+                    ),
+                    peer_location: communities.get_peer_location(
+                        asn,
+                        &as_sequence,
+                        asn_mappings,
+                        timestamp,
+                    ),
+                })
+            })
+            .collect();
+
+        if !has_skip_asn {
+            let Some(peer) = mrt_data.peer_id_map.get_peer(&rib_entry.peer_index) else {
+                mrt_data.handle_error(ParseError::UnknownPeerIndex(rib_entry.peer_index));
+                return;
+            };
+            mrt_data.results.coverage.add_route(
+                mrt_data.collector,
+                peer,
+                &as_sequence,
+                &communities,
+            );
+            check_t1_communities(
+                mrt_data,
+                &as_sequence,
+                &communities,
+                &classifications,
+                &prefix,
+            );
+        }
+
         for asn_1 in as_sequence.iter() {
-            // Skip paths from route collectors which are known to contain inaccurate data
-            if asn_1.is_skip_asn(
-                Path::new(mrt_data.fp)
-                    .file_name()
-                    .unwrap()
-                    .to_str()
-                    .unwrap(),
-            ) {
+            // Skip the rest of paths from route collectors which are known to contain inaccurate data
+            if asn_1.is_skip_asn(basename) {
+                mrt_data.results.file_stats.add_skipped_skip_asn();
                 return;
             }
 
@@ -104,10 +201,24 @@ pub mod mrt_parser {
                 let pos_2 = pos_1 + 1;
                 let asn_2 = &as_sequence[pos_2];
                 if asn_2.is_t1_asn() {
-                    let route =
-                        build_route(mrt_data, rib_entry, asn_1, asn_2, &as_sequence, &prefix);
+                    let route = match build_route(
+                        mrt_data,
+                        rib_entry,
+                        &as_sequence,
+                        pos_1,
+                        &communities,
+                        &classifications,
+                        &prefix,
+                    ) {
+                        Ok(route) => route,
+                        Err(e) => {
+                            // Carry on with the other T1 adjacencies in the path
+                            mrt_data.handle_error(e);
+                            continue;
+                        }
+                    };
 
-                    add_peering(mrt_data.global_peerings, &route);
+                    add_peering(mrt_data, &route);
 
                     if pos_2 == as_sequence.len() - 1 {
                         // Last ASN in the path
@@ -116,44 +227,109 @@ pub mod mrt_parser {
 
                     let asn_3 = &as_sequence[pos_2 + 1];
                     if asn_3.is_t1_asn() {
-                        let route =
-                            build_route(mrt_data, rib_entry, asn_2, asn_3, &as_sequence, &prefix);
-
-                        add_peering(mrt_data.global_peerings, &route);
-                        add_triple_t1_path(
-                            mrt_data.triple_t1_paths,
-                            Vec::from([asn_1.clone(), asn_2.clone(), asn_3.clone()]),
-                            &route,
-                        );
+                        let route = match build_route(
+                            mrt_data,
+                            rib_entry,
+                            &as_sequence,
+                            pos_2,
+                            &communities,
+                            &classifications,
+                            &prefix,
+                        ) {
+                            Ok(route) => route,
+                            Err(e) => {
+                                mrt_data.handle_error(e);
+                                continue;
+                            }
+                        };
+
+                        add_peering(mrt_data, &route);
+                        let triple_t1_path =
+                            Vec::from([asn_1.clone(), asn_2.clone(), asn_3.clone()]);
+                        let triple_t1_paths = &mut mrt_data.results.triple_t1_paths;
+                        if !triple_t1_paths.has_path(&triple_t1_path) {
+                            triple_t1_paths.add_path(triple_t1_path, route);
+                        }
                     }
                 }
             }
         }
     }
 
-    /// Return the mapping of peer IDs to peer details
-    pub fn get_peer_id_map(fp: &String) -> PeerTable {
-        let parser = BgpkitParser::new(fp.as_str())
-            .unwrap_or_else(|_| panic!("Unable to parse MRT file {}", fp));
+    /// For each T1 ASN in the path, check its per-neighbor communities against the ASN
+    /// which follows it, record its relationship with that ASN if the route is classified,
+    /// and if it can't be fully classified, record its unmapped communities
+    fn check_t1_communities(
+        mrt_data: &mut MrtData,
+        as_sequence: &[MrtAsn],
+        communities: &StandardCommunities,
+        classifications: &[Option<Classification>],
+        prefix: &IpNet,
+    ) {
+        let timestamp = mrt_data.mrt_entry.common_header.timestamp;
+        let asn_mappings = mrt_data.asn_mappings;
+        for (pos, (asn, classification)) in as_sequence.iter().zip(classifications).enumerate() {
+            let Some(Classification {
+                peer_type,
+                peer_location,
+            }) = classification
+            else {
+                continue;
+            };
+            let next_asn = as_sequence.get(pos + 1);
+            if let Some((community, neighbor)) =
+                communities.get_neighbor(asn, as_sequence, asn_mappings, timestamp)
+            {
+                mrt_data
+                    .results
+                    .neighbor_checks
+                    .add_route(asn, community, neighbor, next_asn, prefix);
+            }
+
+            if let Some(next_asn) = next_asn
+                && (**peer_type != PeerType::NoneFound
+                    || **peer_location != PeerLocation::NONE_FOUND)
+            {
+                mrt_data.results.known_relationships.add_route(
+                    asn,
+                    next_asn,
+                    peer_type,
+                    peer_location,
+                );
+            }
+            if **peer_type == PeerType::NoneFound || **peer_location == PeerLocation::NONE_FOUND {
+                let unmapped = communities
+                    .with_asn(asn)
+                    .filter(|community| !asn_mappings.has_mapping(asn, community, timestamp))
+                    .map(|community| *community.get_value());
+                mrt_data
+                    .results
+                    .unmapped_communities
+                    .add_route(asn, unmapped, next_asn, prefix);
+            }
+        }
+    }
 
-        let mrt_record = parser
-            .into_record_iter()
-            .next()
-            .unwrap_or_else(|| panic!("Unable to extract first record from {}", fp));
+    /// Return the mapping of peer IDs to peer details, from the first record of a RIB file.
+    /// Only the peer index table is consumed, so the same iterator can be used to parse the RIB entries.
+    pub fn get_peer_id_map(
+        records: &mut impl Iterator<Item = MrtRecord>,
+    ) -> Result<PeerTable, ParseError> {
+        let mrt_record = records.next().ok_or(ParseError::MissingFirstRecord)?;
 
         if let MrtMessage::TableDumpV2Message(TableDumpV2Message::PeerIndexTable(peer_table)) =
             &mrt_record.message
         {
-            PeerTable::from(&peer_table.id_peer_map)
+            Ok(PeerTable::from(&peer_table.id_peer_map))
         } else {
-            panic!("Couldn't extract peer table from table dump in {}", fp);
+            Err(ParseError::MissingPeerTable)
         }
     }
 
     /// Return the RIB entry in the MRT record.
     /// This is either a single v4 prefix or a single v6 prefix
     /// Skip default route.
-    fn get_rib_entries<'a>(mrt_entry: &'a MrtRecord, fp: &String) -> Option<&'a RibAfiEntries> {
+    fn get_rib_entries(mrt_entry: &MrtRecord) -> Result<Option<&RibAfiEntries>, ParseError> {
         let v4_default: IpNet = "0.0.0.0/0".parse().unwrap();
         let v6_default: IpNet = "::/0".parse().unwrap();
 
@@ -163,56 +339,39 @@ pub mod mrt_parser {
             match rib_entries.rib_type {
                 TableDumpV2Type::RibIpv4Unicast | TableDumpV2Type::RibIpv4UnicastAddPath => {
                     if rib_entries.prefix.prefix == v4_default {
-                        return None;
+                        return Ok(None);
                     }
-                    Some(rib_entries)
+                    Ok(Some(rib_entries))
                 }
                 TableDumpV2Type::RibIpv6Unicast | TableDumpV2Type::RibIpv6UnicastAddPath => {
                     if rib_entries.prefix.prefix == v6_default {
-                        return None;
+                        return Ok(None);
                     }
-                    Some(rib_entries)
+                    Ok(Some(rib_entries))
                 }
-                _ => panic!(
-                    "Unexpected record type {:#?} in file {}",
-                    mrt_entry.message, fp
-                ),
+                _ => Err(ParseError::UnexpectedRibType(format!(
+                    "{:?}",
+                    rib_entries.rib_type
+                ))),
             }
         } else {
-            panic!(
-                "MRT record isn't of type RibAfi in file {}: {:#?}",
-                fp, mrt_entry
-            );
+            Err(ParseError::NotRibAfi)
         }
     }
 
     /// Return the next-nop which can be v4 or v6.
     /// If v6 LL and GUA nh exists, GUA is returned.
-    fn get_next_hop(rib_entry: &RibEntry, fp: &String) -> IpAddr {
-        if rib_entry.attributes.get_reachable_nlri().is_some() {
-            let mp_nlri = rib_entry
-                .attributes
-                .get_reachable_nlri()
-                .unwrap_or_else(|| {
-                    panic!(
-                        "Couldn't extract MP NLRI in file {} for: {:#?}",
-                        fp, rib_entry
-                    )
-                });
-
-            assert!(
-                mp_nlri.is_ipv6(),
-                "MP NLRI is used for non-IPv6 info in file {} for: {:#?}",
-                fp,
-                rib_entry
-            );
-
-            mp_nlri.next_hop_addr()
+    fn get_next_hop(rib_entry: &RibEntry, prefix: &IpNet) -> Result<IpAddr, ParseError> {
+        if let Some(mp_nlri) = rib_entry.attributes.get_reachable_nlri() {
+            if !mp_nlri.is_ipv6() {
+                return Err(ParseError::NonIpv6MpNlri(*prefix));
+            }
+            Ok(mp_nlri.next_hop_addr())
         } else {
             rib_entry
                 .attributes
                 .next_hop()
-                .unwrap_or_else(|| panic!("No next-hop in file {} for: {:#?}", fp, rib_entry))
+                .ok_or(ParseError::MissingNextHop(*prefix))
         }
     }
 
@@ -236,85 +395,86 @@ pub mod mrt_parser {
     /// The likelihood of there being more than on AS Sequence (because the path)
     /// is longer than 255 ASNs is incredibly low. Also, because we're looking for
     /// T1 AS path, we're not interested in AS_SETs.
-    fn get_as_sequence(rib_entry: &RibEntry, fp: &String) -> Vec<MrtAsn> {
+    fn get_as_sequence(rib_entry: &RibEntry, prefix: &IpNet) -> Result<Vec<MrtAsn>, ParseError> {
         let as_path_segments = &rib_entry
             .attributes
             .as_path()
-            .unwrap_or_else(|| {
-                panic!(
-                    "Unable to unpack AS Path segments from RIB entry in {}:  {:#?}",
-                    fp, rib_entry
-                )
-            })
+            .ok_or(ParseError::MissingAsPath(*prefix))?
             .segments;
 
         for path_seg in as_path_segments {
             if let AsPathSegment::AsSequence(asns) = path_seg {
-                return asns
+                return Ok(asns
                     .iter()
-                    .map(|a| MrtAsn::new(a.clone()))
-                    .collect::<Vec<MrtAsn>>();
+                    .map(|a| MrtAsn::new(*a))
+                    .collect::<Vec<MrtAsn>>());
             }
         }
 
-        Vec::<MrtAsn>::new()
+        Ok(Vec::<MrtAsn>::new())
     }
 
+    /// Build the route for the peering between the T1 at a position in the path and the ASN
+    /// after it, using the classification of the T1 from its communities
     fn build_route(
         mrt_data: &MrtData,
         rib_entry: &RibEntry,
-        local_asn: &MrtAsn,
-        peer_asn: &MrtAsn,
-        as_sequence: &Vec<MrtAsn>,
+        as_sequence: &[MrtAsn],
+        pos: usize,
+        communities: &StandardCommunities,
+        classifications: &[Option<Classification>],
         prefix: &IpNet,
-    ) -> Route {
-        let peer = mrt_data.peer_id_map.get_peer(&rib_entry.peer_index);
-        let next_hop = get_next_hop(rib_entry, mrt_data.fp);
-        let communities = get_communities(rib_entry);
-        let peer_location = communities.get_peer_location(local_asn, mrt_data.asn_mappings);
-        let peer_type = communities.get_peer_type(local_asn, mrt_data.asn_mappings);
+    ) -> Result<Route, ParseError> {
+        let peer = mrt_data
+            .peer_id_map
+            .get_peer(&rib_entry.peer_index)
+            .ok_or(ParseError::UnknownPeerIndex(rib_entry.peer_index))?;
+        let next_hop = get_next_hop(rib_entry, prefix)?;
+        let local_asn = &as_sequence[pos];
+        let Classification {
+            peer_type,
+            peer_location,
+        } = classifications[pos]
+            .as_ref()
+            .expect("peerings are only built for T1 ASNs");
+        let session_type = communities.get_session_type(
+            local_asn,
+            as_sequence,
+            mrt_data.asn_mappings,
+            mrt_data.mrt_entry.common_header.timestamp,
+        );
 
-        Route::new(
+        Ok(Route::new(
             local_asn.clone(),
-            peer_asn.clone(),
-            peer_type.clone(),
-            peer_location.clone(),
+            as_sequence[pos + 1].clone(),
+            (*peer_type).clone(),
+            (*peer_location).clone(),
+            session_type.clone(),
             as_sequence.to_owned(),
             mrt_data.fp.clone(),
             next_hop.to_owned(),
             peer.to_owned(),
             prefix.to_owned(),
             communities.clone(),
-        )
+        ))
     }
 
-    fn add_peering(global_peerings: &Arc<RwLock<PeeringData>>, route: &Route) {
-        if *route.get_peer_type() != PeerType::NoneFound {
-            let has_peering: bool;
-            {
-                let lock = global_peerings.read().unwrap();
-                has_peering = lock.has_peering(route);
-            }
-            if !has_peering {
-                let mut lock = global_peerings.write().unwrap();
-                lock.add_peering(route.clone());
+    fn add_peering(mrt_data: &mut MrtData, route: &Route) {
+        let results = &mut *mrt_data.results;
+        let classified = *route.get_peer_type() != PeerType::NoneFound;
+        results.file_stats.add_t1_adjacency(classified);
+        if classified {
+            results.vantage_points.add_route(mrt_data.collector, route);
+            results.snapshots.add_route(
+                mrt_data.collector,
+                mrt_data.mrt_entry.common_header.timestamp,
+                route,
+            );
+            if !results.peering_data.has_peering(route) {
+                results.peering_data.add_peering(route.clone());
+            } else {
+                results.peering_data.add_session_type(route);
             }
         }
     }
-
-    fn add_triple_t1_path(
-        triple_t1_paths: &Arc<RwLock<TripleT1Paths>>,
-        triple_t1_path: Vec<MrtAsn>,
-        route: &Route,
-    ) {
-        let has_path: bool;
-        {
-            let lock = triple_t1_paths.read().unwrap();
-            has_path = lock.has_path(&triple_t1_path);
-        }
-        if !has_path {
-            let mut lock = triple_t1_paths.write().unwrap();
-            lock.add_path(triple_t1_path, route.clone());
-        }
-    }
 }
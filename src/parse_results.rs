@@ -0,0 +1,42 @@
+pub mod parse_result {
+    use crate::coverage::collector_coverage::CollectorCoverage;
+    use crate::inference::community_inference::KnownRelationships;
+    use crate::neighbors::neighbor_checks::NeighborChecks;
+    use crate::peerings::peering_data::PeeringData;
+    use crate::run_stats::parse_stats::FileStats;
+    use crate::snapshots::snapshot_comparison::Snapshots;
+    use crate::triple_paths::triple_t1_paths::TripleT1Paths;
+    use crate::unmapped::unmapped_communities::UnmappedCommunities;
+    use crate::vantage_points::vantage_point_ranking::VantagePoints;
+
+    /// Results from parsing some or all of one or more RIB files.
+    /// Each rayon worker accumulates its own results without taking any locks,
+    /// and they are merged once parsing has finished.
+    #[derive(Debug, Default)]
+    pub struct ParseResults {
+        pub file_stats: FileStats,
+        pub peering_data: PeeringData,
+        pub triple_t1_paths: TripleT1Paths,
+        pub coverage: CollectorCoverage,
+        pub vantage_points: VantagePoints,
+        pub snapshots: Snapshots,
+        pub neighbor_checks: NeighborChecks,
+        pub unmapped_communities: UnmappedCommunities,
+        pub known_relationships: KnownRelationships,
+    }
+
+    impl ParseResults {
+        pub fn merge(mut self, other: ParseResults) -> Self {
+            self.file_stats.add(&other.file_stats);
+            self.peering_data.merge(other.peering_data);
+            self.triple_t1_paths.merge(other.triple_t1_paths);
+            self.coverage.merge(other.coverage);
+            self.vantage_points.merge(other.vantage_points);
+            self.snapshots.merge(other.snapshots);
+            self.neighbor_checks.merge(other.neighbor_checks);
+            self.unmapped_communities.merge(other.unmapped_communities);
+            self.known_relationships.merge(other.known_relationships);
+            self
+        }
+    }
+}
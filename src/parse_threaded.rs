@@ -1,18 +1,33 @@
 pub mod threaded_parser {
     use crate::args::cli_args::CliArgs;
     use crate::comm_mappings::community_mappings::AsnMappings;
-    use crate::parse_mrt::mrt_parser::{MrtData, get_peer_id_map, parse_mrt_entry};
-    use crate::peerings::peering_data::PeeringData;
+    use crate::inference::community_inference::{AsRelationships, DraftMappings};
+    use crate::parse_errors::parse_error::{ParseError, ParseErrors};
+    use crate::parse_mrt::mrt_parser::{MrtData, get_peer_id_map, handle_error, parse_mrt_entry};
+    use crate::parse_results::parse_result::ParseResults;
+    use crate::progress::parse_progress::ParseProgress;
     use crate::ribs::rib_getter::RibFile;
-    use crate::triple_paths::triple_t1_paths::TripleT1Paths;
-    use bgpkit_parser::BgpkitParser;
-    use log::{debug, info};
+    use crate::run_stats::parse_stats::RunStats;
+    use crate::scheduler::file_scheduler::FileScheduler;
+    use bgpkit_parser::error::ParserError;
+    use bgpkit_parser::{BgpkitParser, MrtRecord};
+    use indicatif::MultiProgress;
+    use itertools::Itertools;
+    use log::{debug, error, info};
     use rayon::iter::{IntoParallelIterator, ParallelIterator};
-    use rayon::prelude::*;
-    use std::sync::{Arc, RwLock};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex, RwLock, mpsc};
+    use std::time::Instant;
+
+    // Number of MRT records handed to a thread at a time, when splitting a file across threads
+    const RECORD_BATCH_SIZE: usize = 1024;
 
     /// Setup and call parallel parsing of RIB files
-    pub fn init_parallel_parsing(rib_files: &Vec<RibFile>, args: &CliArgs) {
+    pub fn init_parallel_parsing(
+        rib_files: &[RibFile],
+        args: &CliArgs,
+        multi_progress: &MultiProgress,
+    ) {
         info!("Going to parse {} RIB files", rib_files.len());
         debug!(
             "{:?}",
@@ -23,69 +38,276 @@ pub mod threaded_parser {
         );
 
         let asn_mappings = AsnMappings::default();
-        let peering_data = Arc::new(RwLock::new(PeeringData::default()));
-        let triple_t1_paths = Arc::new(RwLock::new(TripleT1Paths::default()));
+        let as_relationships = args.as_relationships.as_ref().map(|filename| {
+            AsRelationships::from_file(filename).unwrap_or_else(|e| {
+                error!("{}", e);
+                std::process::exit(1);
+            })
+        });
+        let parse_errors = Arc::new(RwLock::new(ParseErrors::default()));
+        let run_stats = Arc::new(RwLock::new(RunStats::default()));
+
+        let threads = rayon::current_num_threads();
+        let threads_per_file = args
+            .get_strategy()
+            .get_threads_per_file(threads, rib_files.len());
+        info!(
+            "Parsing {} files at a time, with {} threads per file",
+            (threads / threads_per_file).min(rib_files.len()),
+            threads_per_file
+        );
+
+        let start = Instant::now();
+        let results = parse_rib_files(
+            rib_files,
+            threads_per_file,
+            &asn_mappings,
+            &parse_errors,
+            &run_stats,
+            multi_progress,
+            args.stream(),
+            args.strict,
+        );
+        run_stats.write().unwrap().set_wall_time(start.elapsed());
+
+        let peering_data = results.peering_data.roll_up(args.location_level);
+        debug! {"{:#?}", peering_data};
+        peering_data.to_file(&args.peering_data);
+
+        debug! {"{:#?}", results.triple_t1_paths};
+        results.triple_t1_paths.to_file(&args.triple_t1_paths);
 
-        parse_rib_files(rib_files, &asn_mappings, &peering_data, &triple_t1_paths);
+        run_stats.read().unwrap().to_file(&args.run_stats);
 
-        debug! {"{:#?}", peering_data.read().unwrap()};
-        peering_data.read().unwrap().to_file(&args.peering_data);
+        results.coverage.log_summary();
+        results.coverage.to_file(&args.coverage);
+        results
+            .vantage_points
+            .to_file(&args.vantage_points, args.location_level);
+        results
+            .snapshots
+            .to_file(&args.snapshots, args.location_level);
+        results.neighbor_checks.log_summary();
+        results.neighbor_checks.to_file(&args.neighbor_checks);
+        results
+            .unmapped_communities
+            .to_file(&args.unmapped_communities);
+        if let Some(draft_mappings) = &args.draft_mappings {
+            DraftMappings::infer(
+                &results.unmapped_communities,
+                &results.known_relationships,
+                as_relationships.as_ref(),
+                args.location_level,
+            )
+            .to_file(draft_mappings);
+        }
 
-        debug! {"{:#?}", triple_t1_paths.read().unwrap()};
-        triple_t1_paths
-            .read()
-            .unwrap()
-            .to_file(&args.triple_t1_paths);
+        parse_errors.read().unwrap().log_summary();
     }
 
-    /// Parse RIB files using multithreading
+    /// Parse RIB files using multithreading. Each worker takes the largest remaining file,
+    /// and merges its results with the other files it has parsed, then the workers' results are merged.
+    #[allow(clippy::too_many_arguments)]
     fn parse_rib_files(
-        rib_files: &Vec<RibFile>,
+        rib_files: &[RibFile],
+        threads_per_file: usize,
         asn_mappings: &AsnMappings,
-        peering_data: &Arc<RwLock<PeeringData>>,
-        triple_t1_paths: &Arc<RwLock<TripleT1Paths>>,
-    ) {
-        // Spin up a thread per file for parsing
-        rib_files.into_par_iter().for_each(|rib_file| {
-            let fp = rib_file.get_filename();
-            info!("Parsing {}", fp);
-            let peer_id_map = get_peer_id_map(fp);
-            debug!("Peer Map for {}: {:#?}\n", fp, peer_id_map);
-
-            let parser =
-                BgpkitParser::new(fp.as_str()).unwrap_or_else(|_| panic!("Unable to parse {}", fp));
-
-            if rib_files.len() == 1 {
-                // If there is only one file, parse that file across all available threads
-                parser
-                    .into_record_iter()
-                    .skip(1)
-                    .par_bridge()
-                    .for_each(|mrt_entry| {
-                        parse_mrt_entry(MrtData::new(
-                            &mrt_entry,
-                            &Arc::clone(&peering_data),
-                            &Arc::clone(&triple_t1_paths),
-                            &peer_id_map,
-                            &asn_mappings,
-                            fp,
-                        ))
-                    });
-            } else {
-                // If there are multiple files, just parse this file in this thread
-                parser.into_record_iter().skip(1).for_each(|mrt_entry| {
-                    parse_mrt_entry(MrtData::new(
-                        &mrt_entry,
-                        &Arc::clone(&peering_data),
-                        &Arc::clone(&triple_t1_paths),
-                        &peer_id_map,
+        parse_errors: &Arc<RwLock<ParseErrors>>,
+        run_stats: &Arc<RwLock<RunStats>>,
+        multi_progress: &MultiProgress,
+        stream: bool,
+        strict: bool,
+    ) -> ParseResults {
+        let scheduler = FileScheduler::new(rib_files, stream);
+        let progress =
+            ParseProgress::new(multi_progress, rib_files.len(), scheduler.get_total_size());
+
+        // Spin up a worker per file which can be parsed at once, each parsing files until there are none left
+        let workers = (rayon::current_num_threads() / threads_per_file).max(1);
+        let results = (0..workers)
+            .into_par_iter()
+            .map(|_| {
+                let mut worker_results = ParseResults::default();
+                while let Some((rib_file, size)) = scheduler.next_file() {
+                    let fp = rib_file.get_filename();
+                    info!("Parsing {}", fp);
+                    let start = Instant::now();
+                    let bar = progress.add_file(fp, size);
+                    let mut results = parse_rib_file(
+                        threads_per_file,
+                        rib_file,
                         asn_mappings,
+                        parse_errors,
+                        &scheduler,
+                        progress.track_reads(&bar),
+                        stream,
+                        strict,
+                    );
+                    progress.finish_file(bar);
+
+                    // Stats are reported per file, so they aren't merged with other files
+                    let mut file_stats = std::mem::take(&mut results.file_stats);
+                    file_stats.set_file_time(start.elapsed());
+                    if let Some(file_errors) = parse_errors.read().unwrap().get_file_errors(fp) {
+                        file_stats.set_errors(file_errors.total());
+                    }
+                    run_stats.write().unwrap().add_file_stats(
                         fp,
-                    ))
-                });
+                        &rib_file.get_collector(),
+                        file_stats,
+                    );
+                    info!("Parsed {}", fp,);
+                    worker_results = worker_results.merge(results);
+                }
+                scheduler.set_idle();
+                worker_results
+            })
+            .reduce(ParseResults::default, ParseResults::merge);
+
+        progress.finish();
+        results
+    }
+
+    /// Parse a single RIB file, either decoding it in the current thread and parsing it in
+    /// `threads - 1` others, or entirely within the current thread.
+    /// When streaming, the file is read from its URL instead of the local filename.
+    /// Progress is reported to the callback as the file is read.
+    #[allow(clippy::too_many_arguments)]
+    fn parse_rib_file(
+        threads: usize,
+        rib_file: &RibFile,
+        asn_mappings: &AsnMappings,
+        parse_errors: &Arc<RwLock<ParseErrors>>,
+        scheduler: &FileScheduler,
+        track_reads: impl Fn(u64, u64) + Send + 'static,
+        stream: bool,
+        strict: bool,
+    ) -> ParseResults {
+        let fp = rib_file.get_filename();
+        let path = if stream { &rib_file.url } else { fp };
+        let collector = rib_file.get_collector();
+        let records = match oneio::get_reader_with_progress(path, track_reads) {
+            Ok((reader, _)) => BgpkitParser::from_reader(reader).into_fallible_record_iter(),
+            Err(e) => {
+                handle_error(
+                    fp,
+                    ParseError::OpenFile(e.to_string()),
+                    parse_errors,
+                    strict,
+                );
+                return ParseResults::default();
             }
+        };
 
-            info!("Parsed {}", fp,);
-        });
+        // Records which can't be parsed are counted as errors and skipped. A read error
+        // means the rest of the file is lost, e.g. it's truncated, so stops parsing it.
+        let mut records = records
+            .map_while(|record| match record {
+                Ok(record) => Some(Some(record)),
+                Err(e) => {
+                    let (error, can_continue) = match e.error {
+                        ParserError::IoError(e) | ParserError::EofError(e) => {
+                            (ParseError::ReadFile(e.to_string()), false)
+                        }
+                        e => (ParseError::InvalidRecord(e.to_string()), true),
+                    };
+                    handle_error(fp, error, parse_errors, strict);
+                    can_continue.then_some(None)
+                }
+            })
+            .flatten();
+
+        let peer_id_map = match get_peer_id_map(&mut records) {
+            Ok(peer_id_map) => peer_id_map,
+            Err(e) => {
+                handle_error(fp, e, parse_errors, strict);
+                return ParseResults::default();
+            }
+        };
+        debug!("Peer Map for {}: {:#?}\n", fp, peer_id_map);
+
+        let parse_batch = |mut results: ParseResults, batch: Vec<MrtRecord>| {
+            for mrt_entry in &batch {
+                parse_mrt_entry(MrtData::new(
+                    mrt_entry,
+                    &peer_id_map,
+                    asn_mappings,
+                    fp,
+                    parse_errors,
+                    &mut results,
+                    &collector,
+                    strict,
+                ))
+            }
+            results
+        };
+
+        if threads > 1 {
+            // If the file has more than one thread, this thread only decodes records, handing
+            // batches of them through a bounded channel to the file's other threads, which
+            // parse them. Each parsing thread accumulates its own results so that they don't
+            // contend on shared locks, and the file never uses more than its share of threads.
+            let (sender, receiver) = mpsc::sync_channel::<Vec<MrtRecord>>(threads * 2);
+            let receiver = Arc::new(Mutex::new(receiver));
+            let thread_results = Mutex::new(Vec::<ParseResults>::new());
+            rayon::scope(|scope| {
+                for _ in 1..threads {
+                    let (receiver, parse_batch, thread_results) =
+                        (Arc::clone(&receiver), &parse_batch, &thread_results);
+                    scope.spawn(move |_| {
+                        let mut results = ParseResults::default();
+                        loop {
+                            let Ok(batch) = receiver.lock().unwrap().recv() else {
+                                break;
+                            };
+                            results = parse_batch(results, batch);
+                        }
+                        thread_results.lock().unwrap().push(results);
+                    });
+                }
+                // Once every parsing thread has stopped, e.g. panicked with --strict, the
+                // receiver is dropped and decoding stops rather than blocking on a full channel
+                drop(receiver);
+                for batch in &records.chunks(RECORD_BATCH_SIZE) {
+                    if sender.send(batch.collect()).is_err() {
+                        break;
+                    }
+                }
+                drop(sender);
+            });
+            thread_results
+                .into_inner()
+                .unwrap()
+                .into_iter()
+                .fold(ParseResults::default(), ParseResults::merge)
+        } else {
+            // Otherwise parse this file in this thread. Once other threads
+            // have run out of files to parse, hand them batches of records from this file,
+            // keeping no more batches queued than there are idle threads.
+            let queued_batches = AtomicUsize::new(0);
+            let helper_results = Mutex::new(ParseResults::default());
+            let results = rayon::scope(|scope| {
+                let mut results = ParseResults::default();
+                for batch in &records.chunks(RECORD_BATCH_SIZE) {
+                    let batch: Vec<MrtRecord> = batch.collect();
+                    if queued_batches.load(Ordering::Relaxed) < scheduler.get_idle_threads() {
+                        queued_batches.fetch_add(1, Ordering::Relaxed);
+                        let (parse_batch, queued_batches, helper_results) =
+                            (&parse_batch, &queued_batches, &helper_results);
+                        scope.spawn(move |_| {
+                            let batch_results = parse_batch(ParseResults::default(), batch);
+                            let mut helper_results = helper_results.lock().unwrap();
+                            *helper_results =
+                                std::mem::take(&mut *helper_results).merge(batch_results);
+                            queued_batches.fetch_sub(1, Ordering::Relaxed);
+                        });
+                    } else {
+                        results = parse_batch(results, batch);
+                    }
+                }
+                results
+            });
+            results.merge(helper_results.into_inner().unwrap())
+        }
     }
 }
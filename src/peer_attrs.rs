@@ -1,5 +1,9 @@
 pub mod peer_data {
-    use serde::Serialize;
+    use crate::args::cli_args::LocationLevel;
+    use crate::parse_errors::parse_error::ParseError;
+    use serde::{Serialize, Serializer};
+    use std::fmt;
+    use std::str::FromStr;
 
     #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize)]
     pub enum PeerType {
@@ -10,8 +14,21 @@ pub mod peer_data {
         NoneFound,
     }
 
-    #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize)]
-    pub enum PeerLocation {
+    /// How the route was learned, where the operator tags it
+    #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+    pub enum SessionType {
+        /// A bilateral session across a public peering exchange
+        PublicIxp,
+        /// A private network interconnect
+        PrivateInterconnect,
+        /// An IXP route server
+        RouteServer,
+        NoneFound,
+    }
+
+    /// The top level of a peer location
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum Continent {
         Africa,
         AsiaPac,
         Europe,
@@ -20,4 +37,191 @@ pub mod peer_data {
         SouthAmerica,
         NoneFound,
     }
+
+    impl Continent {
+        const ALL: [Continent; 7] = [
+            Self::Africa,
+            Self::AsiaPac,
+            Self::Europe,
+            Self::MiddleEast,
+            Self::NorthAmerica,
+            Self::SouthAmerica,
+            Self::NoneFound,
+        ];
+    }
+
+    impl fmt::Display for Continent {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{:?}", self)
+        }
+    }
+
+    /// Where a peering is located: a continent > region > country > metro hierarchy,
+    /// e.g. "AsiaPac/SouthAsia/IN". Mappings can stop at any level, and levels in
+    /// between may be unknown, e.g. "NorthAmerica//US" is a country with no region.
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    pub struct PeerLocation {
+        continent: Continent,
+        region: Option<String>,
+        country: Option<String>,
+        metro: Option<String>,
+    }
+
+    impl From<Continent> for PeerLocation {
+        fn from(continent: Continent) -> Self {
+            Self {
+                continent,
+                region: None,
+                country: None,
+                metro: None,
+            }
+        }
+    }
+
+    impl FromStr for PeerLocation {
+        type Err = ParseError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let invalid = || ParseError::InvalidLocation(s.to_owned());
+            let mut levels = s.split('/').map(|level| level.trim());
+            let continent = levels.next().ok_or_else(invalid)?;
+            let continent = Continent::ALL
+                .into_iter()
+                .find(|c| c.to_string() == continent)
+                .ok_or_else(invalid)?;
+
+            let mut level = || levels.next().filter(|l| !l.is_empty()).map(String::from);
+            let location = Self {
+                continent,
+                region: level(),
+                country: level(),
+                metro: level(),
+            };
+            if levels.next().is_some() {
+                return Err(invalid());
+            }
+            Ok(location)
+        }
+    }
+
+    impl fmt::Display for PeerLocation {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let levels = [&self.region, &self.country, &self.metro];
+            let depth = levels
+                .iter()
+                .rposition(|l| l.is_some())
+                .map_or(0, |i| i + 1);
+            write!(f, "{}", self.continent)?;
+            for level in &levels[..depth] {
+                write!(f, "/{}", level.as_deref().unwrap_or(""))?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Serialised as a string, so locations can be used as JSON keys
+    impl Serialize for PeerLocation {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl PeerLocation {
+        pub const NONE_FOUND: PeerLocation = PeerLocation {
+            continent: Continent::NoneFound,
+            region: None,
+            country: None,
+            metro: None,
+        };
+
+        /// Drop the levels below the given level, so peerings can be grouped at that level
+        pub fn roll_up(&self, level: LocationLevel) -> Self {
+            let keep = |l: LocationLevel, value: &Option<String>| {
+                if level >= l { value.clone() } else { None }
+            };
+            Self {
+                continent: self.continent,
+                region: keep(LocationLevel::Region, &self.region),
+                country: keep(LocationLevel::Country, &self.country),
+                metro: keep(LocationLevel::Metro, &self.metro),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn location(s: &str) -> PeerLocation {
+            s.parse().unwrap()
+        }
+
+        #[test]
+        fn parses_each_level_of_the_hierarchy() {
+            assert_eq!(location("Europe"), PeerLocation::from(Continent::Europe));
+            let metro = location("AsiaPac/SouthAsia/IN/BOM");
+            assert_eq!(metro.continent, Continent::AsiaPac);
+            assert_eq!(metro.region.as_deref(), Some("SouthAsia"));
+            assert_eq!(metro.country.as_deref(), Some("IN"));
+            assert_eq!(metro.metro.as_deref(), Some("BOM"));
+
+            // Levels in between may be unknown
+            let country = location("NorthAmerica//US");
+            assert_eq!(country.region, None);
+            assert_eq!(country.country.as_deref(), Some("US"));
+        }
+
+        #[test]
+        fn formats_as_parsed() {
+            for s in [
+                "Europe",
+                "AsiaPac/SouthAsia",
+                "AsiaPac/SouthAsia/IN",
+                "AsiaPac/SouthAsia/IN/BOM",
+                "NorthAmerica//US",
+                "NoneFound",
+            ] {
+                assert_eq!(location(s).to_string(), s);
+            }
+        }
+
+        #[test]
+        fn rejects_invalid_locations() {
+            for s in ["", "Atlantis", "europe", "Europe/West/DE/FRA/1"] {
+                assert!(
+                    matches!(
+                        s.parse::<PeerLocation>(),
+                        Err(ParseError::InvalidLocation(_))
+                    ),
+                    "{}",
+                    s
+                );
+            }
+        }
+
+        #[test]
+        fn rolls_up_to_a_level() {
+            let metro = location("AsiaPac/SouthAsia/IN/BOM");
+            let roll_up = |level| metro.roll_up(level).to_string();
+            assert_eq!(roll_up(LocationLevel::Continent), "AsiaPac");
+            assert_eq!(roll_up(LocationLevel::Region), "AsiaPac/SouthAsia");
+            assert_eq!(roll_up(LocationLevel::Country), "AsiaPac/SouthAsia/IN");
+            assert_eq!(roll_up(LocationLevel::Metro), "AsiaPac/SouthAsia/IN/BOM");
+
+            // Rolling up never adds levels, and keeps unknown levels in between
+            assert_eq!(
+                location("Europe").roll_up(LocationLevel::Metro),
+                location("Europe")
+            );
+            assert_eq!(
+                location("NorthAmerica//US/NYC")
+                    .roll_up(LocationLevel::Country)
+                    .to_string(),
+                "NorthAmerica//US"
+            );
+        }
+    }
 }
@@ -1,36 +1,59 @@
 pub mod peering_data {
+    use crate::args::cli_args::LocationLevel;
     use crate::file::ensure_dir;
     use crate::mrt_asn::asn::MrtAsn;
     use crate::mrt_route::route::{IpVersion, Route};
-    use crate::peer_attrs::peer_data::{PeerLocation, PeerType};
+    use crate::peer_attrs::peer_data::{PeerLocation, PeerType, SessionType};
     use log::info;
     use serde::Serialize;
     use serde_json;
-    use std::collections::HashMap;
+    use std::collections::{BTreeSet, HashMap};
     use std::fs::File;
     use std::io::BufWriter;
 
+    /// Peerings with a single peer type, keyed by IP version, and the session types
+    /// (IXP, PNI or route server) any of their routes were learned over
     #[derive(Debug, Serialize)]
     pub struct PeeringsByVersion {
         peerings_by_ver: HashMap<IpVersion, Route>,
+        session_types: BTreeSet<SessionType>,
     }
 
     impl Default for PeeringsByVersion {
         fn default() -> Self {
-            Self::new(HashMap::<IpVersion, Route>::new())
+            Self::new(
+                HashMap::<IpVersion, Route>::new(),
+                BTreeSet::<SessionType>::new(),
+            )
         }
     }
 
     impl PeeringsByVersion {
-        pub fn new(peerings_by_ver: HashMap<IpVersion, Route>) -> Self {
-            Self { peerings_by_ver }
+        pub fn new(
+            peerings_by_ver: HashMap<IpVersion, Route>,
+            session_types: BTreeSet<SessionType>,
+        ) -> Self {
+            Self {
+                peerings_by_ver,
+                session_types,
+            }
         }
 
         pub fn from(route: Route) -> Self {
-            Self::new(HashMap::from([(route.get_ip_version().clone(), route)]))
+            let mut peerings = Self::default();
+            peerings.add_peering(route);
+            peerings
+        }
+
+        /// Record the session type of a route, for a peering which may already be known
+        pub fn add_session_type(&mut self, route: &Route) {
+            if *route.get_session_type() != SessionType::NoneFound {
+                self.session_types.insert(route.get_session_type().clone());
+            }
         }
 
         pub fn add_peering(&mut self, route: Route) {
+            self.add_session_type(&route);
             if self.has_peering(&route) {
                 return;
             }
@@ -41,6 +64,14 @@ pub mod peering_data {
         pub fn has_peering(&self, route: &Route) -> bool {
             self.peerings_by_ver.contains_key(&route.get_ip_version())
         }
+
+        /// Merge peerings found by another thread, keeping existing routes
+        pub fn merge(&mut self, other: PeeringsByVersion) {
+            for (ip_version, route) in other.peerings_by_ver {
+                self.peerings_by_ver.entry(ip_version).or_insert(route);
+            }
+            self.session_types.extend(other.session_types);
+        }
     }
 
     /// All peerings for a single location, keyed by peer type
@@ -67,6 +98,10 @@ pub mod peering_data {
             )]))
         }
 
+        pub fn add_session_type(&mut self, route: &Route) {
+            self.get_type_peerings_mut(route).add_session_type(route);
+        }
+
         pub fn add_peering(&mut self, route: Route) {
             if !self.has_peerings_with_type(&route) {
                 self.peerings_in_loc.insert(
@@ -97,6 +132,15 @@ pub mod peering_data {
             }
             false
         }
+
+        pub fn merge(&mut self, other: PeeringsInLocation) {
+            for (peer_type, peerings) in other.peerings_in_loc {
+                self.peerings_in_loc
+                    .entry(peer_type)
+                    .or_default()
+                    .merge(peerings);
+            }
+        }
     }
 
     /// All peerings for a single peer ASN, keyed by peer location
@@ -146,6 +190,10 @@ pub mod peering_data {
                 .unwrap()
         }
 
+        pub fn add_session_type(&mut self, route: &Route) {
+            self.get_peerings_in_mut(route).add_session_type(route);
+        }
+
         pub fn has_peerings_in_location(&self, route: &Route) -> bool {
             self.location_peerings
                 .contains_key(route.get_peer_location())
@@ -158,6 +206,28 @@ pub mod peering_data {
             }
             false
         }
+
+        pub fn merge(&mut self, other: LocationPeerings) {
+            for (location, peerings) in other.location_peerings {
+                self.location_peerings
+                    .entry(location)
+                    .or_default()
+                    .merge(peerings);
+            }
+        }
+
+        /// Merge the peerings of locations which are the same at the given level
+        pub fn roll_up(self, level: LocationLevel) -> Self {
+            let mut rolled_up = Self::default();
+            for (location, peerings) in self.location_peerings {
+                rolled_up
+                    .location_peerings
+                    .entry(location.roll_up(level))
+                    .or_default()
+                    .merge(peerings);
+            }
+            rolled_up
+        }
     }
 
     /// Peering data for an ASN, keyed by peer ASN
@@ -203,6 +273,10 @@ pub mod peering_data {
             self.peers.get_mut(route.get_peer_as()).unwrap()
         }
 
+        pub fn add_session_type(&mut self, route: &Route) {
+            self.get_peerings_for_mut(route).add_session_type(route);
+        }
+
         pub fn has_peering_with(&self, route: &Route) -> bool {
             self.peers.contains_key(route.get_peer_as())
         }
@@ -214,6 +288,21 @@ pub mod peering_data {
             }
             false
         }
+
+        pub fn merge(&mut self, other: AsnPeerings) {
+            for (peer_as, peerings) in other.peers {
+                self.peers.entry(peer_as).or_default().merge(peerings);
+            }
+        }
+
+        pub fn roll_up(self, level: LocationLevel) -> Self {
+            Self::new(
+                self.peers
+                    .into_iter()
+                    .map(|(peer_as, peerings)| (peer_as, peerings.roll_up(level)))
+                    .collect(),
+            )
+        }
     }
 
     /// Public API which provides access to all peerings, keyed by local ASN
@@ -250,6 +339,12 @@ pub mod peering_data {
             self.peering_data.get_mut(route.get_local_as()).unwrap()
         }
 
+        /// Record the session type of a route for a peering which is already known.
+        /// The peering must have been added with add_peering first.
+        pub fn add_session_type(&mut self, route: &Route) {
+            self.get_asn_data_mut(route).add_session_type(route);
+        }
+
         fn has_data_for(&self, route: &Route) -> bool {
             self.peering_data.contains_key(route.get_local_as())
         }
@@ -262,6 +357,29 @@ pub mod peering_data {
             false
         }
 
+        /// Merge peerings found by another thread into this one. Where both have a
+        /// route for the same peering, the existing route is kept.
+        pub fn merge(&mut self, other: PeeringData) {
+            for (local_as, peerings) in other.peering_data {
+                self.peering_data
+                    .entry(local_as)
+                    .or_default()
+                    .merge(peerings);
+            }
+        }
+
+        /// Group peerings by location at the given level, e.g. to report peerings per
+        /// continent when some mappings locate them by country. Where more than one
+        /// location has a route for the same peering, an arbitrary route is kept.
+        pub fn roll_up(self, level: LocationLevel) -> Self {
+            Self::new(
+                self.peering_data
+                    .into_iter()
+                    .map(|(local_as, peerings)| (local_as, peerings.roll_up(level)))
+                    .collect(),
+            )
+        }
+
         pub fn to_file(&self, filename: &String) {
             ensure_dir(filename);
             let writer = BufWriter::new(File::create(filename).unwrap());
@@ -0,0 +1,62 @@
+pub mod parse_progress {
+    use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+    const TOTAL_TEMPLATE: &str =
+        "{msg} [{elapsed_precise}] {wide_bar} {bytes}/{total_bytes} ETA {eta}";
+    const FILE_TEMPLATE: &str = "{msg} {wide_bar} {bytes}/{total_bytes} ETA {eta}";
+
+    /// Progress bars for parsing RIB files, one for the whole run and one per file
+    /// currently being parsed. Progress is measured in bytes read from each file,
+    /// before decompression.
+    pub struct ParseProgress {
+        multi: MultiProgress,
+        total: ProgressBar,
+        files: usize,
+        files_parsed: AtomicUsize,
+    }
+
+    impl ParseProgress {
+        pub fn new(multi: &MultiProgress, files: usize, total_bytes: u64) -> Self {
+            let total = multi.add(ProgressBar::new(total_bytes));
+            total.set_style(ProgressStyle::with_template(TOTAL_TEMPLATE).unwrap());
+            total.set_message(format!("Parsed 0/{} files", files));
+            Self {
+                multi: multi.clone(),
+                total,
+                files,
+                files_parsed: AtomicUsize::new(0),
+            }
+        }
+
+        /// Add a progress bar for a file which is about to be parsed
+        pub fn add_file(&self, filename: &str, size: Option<u64>) -> ProgressBar {
+            let bar = self.multi.add(ProgressBar::new(size.unwrap_or(0)));
+            bar.set_style(ProgressStyle::with_template(FILE_TEMPLATE).unwrap());
+            bar.set_message(filename.to_owned());
+            bar
+        }
+
+        /// Return a callback which updates a file's progress bar, and the total, as the file is read
+        pub fn track_reads(&self, bar: &ProgressBar) -> impl Fn(u64, u64) + Send + 'static {
+            let bar = bar.clone();
+            let total = self.total.clone();
+            let last_read = AtomicU64::new(0);
+            move |bytes_read, _| {
+                bar.set_position(bytes_read);
+                total.inc(bytes_read - last_read.swap(bytes_read, Ordering::Relaxed));
+            }
+        }
+
+        pub fn finish_file(&self, bar: ProgressBar) {
+            bar.finish_and_clear();
+            let files_parsed = self.files_parsed.fetch_add(1, Ordering::Relaxed) + 1;
+            self.total
+                .set_message(format!("Parsed {}/{} files", files_parsed, self.files));
+        }
+
+        pub fn finish(&self) {
+            self.total.finish_and_clear();
+        }
+    }
+}
@@ -0,0 +1,813 @@
+pub mod rib_source {
+    use crate::args::cli_args::{DownloadArgs, MrtSource};
+    use crate::dates::ymd::Ymd;
+    use crate::file::{ensure_dir, get_collector_name};
+    use crate::http::http_client::{get_dir_listing, get_text};
+    use crate::parse_errors::parse_error::ParseError;
+    use crate::ribs::rib_getter::RibFile;
+    use bgpkit_broker::{BgpkitBroker, BrokerItem};
+    use log::{debug, info};
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+    use regex::Regex;
+    use serde::{Deserialize, Serialize};
+    use std::fs::{self, File};
+    use std::io::BufWriter;
+    use std::path::{Path, PathBuf};
+
+    // bgproutes.io vantage points which are known to serve broken MRT files
+    static BGPROUTES_SKIP_LIST: [&str; 15] = [
+        "12276_198.35.53.242",
+        "61138_185.121.168.1",
+        "61138_2a06_1280_ae01__1",
+        "61138_2a06_1280_ae02__1",
+        "48390_185.20.3.1",
+        "50620_193.107.126.33",
+        "53763_23.164.232.192",
+        "57777_185.173.128.4",
+        "58308_91.109.120",
+        "205163_2a06_1281_53__1",
+        "206924_185.230.223.3",
+        "208038_93.170.122.204",
+        "215085_2a06_9f41_a100__1",
+        "215085_83.142.31.1",
+        "215828_77.90.25.254",
+    ];
+
+    /// A location which RIB dumps for a specific day can be listed from
+    pub trait RibSource: Sync {
+        fn get_name(&self) -> &str;
+
+        /// Substrings of URLs which should not be downloaded from this source
+        fn get_skip_list(&self) -> &Vec<String>;
+
+        /// Return a list of available RIBs for specific hours of a day (with details like download URL)
+        fn get_rib_list(
+            &self,
+            date: &Ymd,
+            hours: &[u32],
+            dir: &str,
+        ) -> Result<Vec<RibFile>, ParseError>;
+
+        fn is_skipped(&self, url: &str) -> bool {
+            self.get_skip_list().iter().any(|skip| url.contains(skip))
+        }
+    }
+
+    /// RIS and RouteViews RIBs, as indexed by the BGPKIT broker
+    pub struct BrokerSource {
+        skip_list: Vec<String>,
+    }
+
+    impl BrokerSource {
+        pub fn new(skip_list: Vec<String>) -> Self {
+            Self { skip_list }
+        }
+    }
+
+    impl RibSource for BrokerSource {
+        fn get_name(&self) -> &str {
+            "broker"
+        }
+
+        fn get_skip_list(&self) -> &Vec<String> {
+            &self.skip_list
+        }
+
+        fn get_rib_list(
+            &self,
+            date: &Ymd,
+            hours: &[u32],
+            dir: &str,
+        ) -> Result<Vec<RibFile>, ParseError> {
+            let timestamps: Vec<i64> = hours
+                .iter()
+                .map(|hour| date.to_unix_ts_at(*hour) as i64)
+                .collect();
+            let broker = BgpkitBroker::new()
+                .ts_start(date)
+                .ts_end(format!("{} 23:59:59", date))
+                .data_type("rib");
+            let ribs: Vec<BrokerItem> = broker
+                .query()
+                .map_err(|e| ParseError::BrokerQuery(e.to_string()))?
+                .into_iter()
+                .filter(|rib| timestamps.contains(&rib.ts_start.and_utc().timestamp()))
+                .collect();
+            debug!("Found {} MRT files for date {}", ribs.len(), date);
+
+            let mut rib_files = Vec::<RibFile>::new();
+            for rib in ribs {
+                let basename = Path::new(&rib.url).file_name().unwrap().to_str().unwrap();
+
+                let source = if rib.collector_id.starts_with("rrc") {
+                    String::from("ris")
+                } else {
+                    String::from("route-views")
+                };
+
+                let filename = if rib.collector_id.starts_with(&source) {
+                    format!("{}/{}.{}", dir, rib.collector_id, basename)
+                } else {
+                    format!("{}/{}.{}.{}", dir, &source, rib.collector_id, basename)
+                };
+
+                // The broker reports 0 when it hasn't crawled the file for its size
+                let size = u64::try_from(rib.exact_size).ok().filter(|size| *size > 0);
+                let timestamp = rib.ts_start.and_utc().timestamp() as u64;
+                rib_files.push(
+                    RibFile::new(rib.url, filename)
+                        .with_size(size)
+                        .with_timestamp(Some(timestamp)),
+                );
+            }
+
+            Ok(rib_files)
+        }
+    }
+
+    /// An HTTP archive with one directory per collector, and a directory per day
+    /// below that, e.g. https://mrt.bgproutes.io/bgp/<collector>/yyyy/mm/dd/
+    pub struct CollectorDirSource {
+        name: String,
+        url: String,
+        day_path: String,
+        file_regex: String,
+        skip_list: Vec<String>,
+    }
+
+    impl CollectorDirSource {
+        pub fn new(
+            name: &str,
+            url: String,
+            day_path: &str,
+            file_regex: String,
+            skip_list: Vec<String>,
+        ) -> Self {
+            Self {
+                name: name.to_owned(),
+                url: with_trailing_slash(url),
+                day_path: day_path.to_owned(),
+                file_regex,
+                skip_list,
+            }
+        }
+
+        pub fn bgproutes(
+            url: Option<String>,
+            file_regex: Option<String>,
+            mut skip_list: Vec<String>,
+        ) -> Self {
+            skip_list.extend(BGPROUTES_SKIP_LIST.iter().map(|s| s.to_string()));
+            Self::new(
+                "bgproutes",
+                url.unwrap_or(String::from("https://mrt.bgproutes.io/bgp/")),
+                "{yyyy}/{mm}/{dd}/",
+                file_regex.unwrap_or(String::from(r"^rib\.{ts}\.mrt\.bz2$")),
+                skip_list,
+            )
+        }
+
+        pub fn pch(
+            url: Option<String>,
+            file_regex: Option<String>,
+            skip_list: Vec<String>,
+        ) -> Self {
+            Self::new(
+                "pch",
+                url.unwrap_or(String::from(
+                    "https://www.pch.net/resources/Raw_Routing_Data/",
+                )),
+                "{yyyy}/{mm}/{dd}/",
+                file_regex.unwrap_or(String::from(r"(?i)mrt.*rib.*{yyyy}\.?{mm}\.?{dd}\D?{hh}")),
+                skip_list,
+            )
+        }
+    }
+
+    impl RibSource for CollectorDirSource {
+        fn get_name(&self) -> &str {
+            &self.name
+        }
+
+        fn get_skip_list(&self) -> &Vec<String> {
+            &self.skip_list
+        }
+
+        fn get_rib_list(
+            &self,
+            date: &Ymd,
+            hours: &[u32],
+            dir: &str,
+        ) -> Result<Vec<RibFile>, ParseError> {
+            let file_regexes = compile_file_regexes(&self.file_regex, date, hours)?;
+            let collectors: Vec<String> = get_dir_listing(&self.url)?
+                .into_iter()
+                .filter(|link| link.ends_with('/'))
+                .map(|link| link.trim_end_matches('/').to_owned())
+                .filter(|collector| !self.is_skipped(collector))
+                .collect();
+            info!(
+                "Found {} collectors at {}, listing files for {}",
+                collectors.len(),
+                self.url,
+                date
+            );
+
+            // Not every collector has data for every day, so listing failures are expected
+            let rib_files = collectors
+                .into_par_iter()
+                .flat_map_iter(|collector| {
+                    let day_url = format!(
+                        "{}{}/{}",
+                        self.url,
+                        collector,
+                        date.fill_template(&self.day_path, 0)
+                    );
+                    let links = get_dir_listing(&day_url).unwrap_or_else(|e| {
+                        debug!("No files for {}: {}", collector, e);
+                        Vec::new()
+                    });
+                    links
+                        .iter()
+                        .filter_map(|link| {
+                            let timestamp = match_file_regexes(&file_regexes, link)?;
+                            let basename = link.strip_prefix("rib.").unwrap_or(link);
+                            Some(
+                                RibFile::new(
+                                    format!("{}{}", day_url, link),
+                                    format!("{}/{}.{}.rib.{}", dir, self.name, collector, basename),
+                                )
+                                .with_md5(get_md5(&day_url, link, &links))
+                                .with_timestamp(Some(timestamp)),
+                            )
+                        })
+                        .collect::<Vec<RibFile>>()
+                })
+                .collect::<Vec<RibFile>>();
+
+            debug!("Found {} MRT files for date {}", rib_files.len(), date);
+            Ok(rib_files)
+        }
+    }
+
+    /// Any single HTTP directory listing, which may contain date placeholders
+    /// e.g. http://archive.routeviews.org/route-views.amsix/bgpdata/{yyyy}.{mm}/RIBS/
+    pub struct HttpDirSource {
+        url: String,
+        file_regex: String,
+        skip_list: Vec<String>,
+    }
+
+    impl HttpDirSource {
+        pub fn new(url: String, file_regex: Option<String>, skip_list: Vec<String>) -> Self {
+            Self {
+                url: with_trailing_slash(url),
+                file_regex: file_regex
+                    .unwrap_or(String::from(r"(rib|bview)\.{yyyy}{mm}{dd}\.{hh}00")),
+                skip_list,
+            }
+        }
+    }
+
+    impl RibSource for HttpDirSource {
+        fn get_name(&self) -> &str {
+            "http-dir"
+        }
+
+        fn get_skip_list(&self) -> &Vec<String> {
+            &self.skip_list
+        }
+
+        fn get_rib_list(
+            &self,
+            date: &Ymd,
+            hours: &[u32],
+            dir: &str,
+        ) -> Result<Vec<RibFile>, ParseError> {
+            let url = date.fill_template(&self.url, 0);
+            let file_regexes = compile_file_regexes(&self.file_regex, date, hours)?;
+            let host = url
+                .split('/')
+                .nth(2)
+                .and_then(|authority| authority.split(':').next())
+                .unwrap_or("http-dir")
+                .to_owned();
+
+            let links = get_dir_listing(&url)?;
+            let rib_files: Vec<RibFile> = links
+                .iter()
+                .filter_map(|link| {
+                    let timestamp = match_file_regexes(&file_regexes, link)?;
+                    let basename = link.strip_prefix("rib.").unwrap_or(link);
+                    Some(
+                        RibFile::new(
+                            format!("{}{}", url, link),
+                            format!("{}/{}.rib.{}", dir, host, basename),
+                        )
+                        .with_md5(get_md5(&url, link, &links))
+                        .with_timestamp(Some(timestamp)),
+                    )
+                })
+                .collect();
+
+            debug!("Found {} MRT files at {}", rib_files.len(), url);
+            Ok(rib_files)
+        }
+    }
+
+    /// An entry in a manifest of RIB files, so that runs can be reproduced without the
+    /// original source, e.g. in an air-gapped environment with a mirror
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct ManifestEntry {
+        collector: String,
+        // Unix timestamp of the RIB dump
+        timestamp: u64,
+        // HTTP URL, or local path, of the RIB file
+        url: String,
+        // Local filename, relative to the download directory
+        filename: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        md5: Option<String>,
+    }
+
+    impl ManifestEntry {
+        pub fn from(rib_file: &RibFile) -> Self {
+            Self {
+                collector: rib_file.get_collector(),
+                // All sources set the timestamp, so this is never actually defaulted
+                timestamp: rib_file.timestamp.unwrap_or_default(),
+                url: rib_file.url.clone(),
+                filename: Path::new(&rib_file.filename)
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .unwrap_or(&rib_file.filename)
+                    .to_owned(),
+                size: rib_file.size,
+                md5: rib_file.md5.clone(),
+            }
+        }
+
+        /// Parse a "collector,timestamp,url,filename[,size[,md5]]" CSV line
+        fn from_csv(line: &str) -> Result<Self, ParseError> {
+            let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+            let invalid = || ParseError::SourceListing(format!("Invalid manifest line: {}", line));
+            if fields.len() < 4 {
+                return Err(invalid());
+            }
+            let optional = |i: usize| fields.get(i).filter(|f| !f.is_empty());
+            Ok(Self {
+                collector: fields[0].to_owned(),
+                timestamp: fields[1].parse().map_err(|_| invalid())?,
+                url: fields[2].to_owned(),
+                filename: fields[3].to_owned(),
+                size: optional(4)
+                    .map(|f| f.parse())
+                    .transpose()
+                    .map_err(|_| invalid())?,
+                md5: optional(5).map(|f| f.to_string()),
+            })
+        }
+
+        fn to_rib_file(&self, dir: &str) -> RibFile {
+            // Files in a local mirror are parsed in place
+            let filename = if is_local_path(&self.url) {
+                self.url.clone()
+            } else {
+                format!("{}/{}", dir, self.filename)
+            };
+            RibFile::new(self.url.clone(), filename)
+                .with_collector(Some(self.collector.clone()))
+                .with_timestamp(Some(self.timestamp))
+                .with_size(self.size)
+                .with_md5(self.md5.clone())
+        }
+    }
+
+    /// Write the list of RIB files for a run to a JSON manifest
+    pub fn write_manifest(rib_files: &[RibFile], filename: &String) {
+        ensure_dir(filename);
+        let entries: Vec<ManifestEntry> = rib_files.iter().map(ManifestEntry::from).collect();
+        let writer = BufWriter::new(File::create(filename).unwrap());
+        serde_json::to_writer_pretty(writer, &entries).unwrap();
+        info!(
+            "Wrote manifest of {} RIB files to {}",
+            entries.len(),
+            filename
+        );
+    }
+
+    /// A local JSON or CSV manifest of RIB files
+    pub struct ManifestSource {
+        filename: String,
+        skip_list: Vec<String>,
+    }
+
+    impl ManifestSource {
+        pub fn new(filename: String, skip_list: Vec<String>) -> Self {
+            Self {
+                filename,
+                skip_list,
+            }
+        }
+
+        fn read_entries(&self) -> Result<Vec<ManifestEntry>, ParseError> {
+            let content = fs::read_to_string(&self.filename).map_err(|e| {
+                ParseError::SourceListing(format!("Unable to read {}: {}", self.filename, e))
+            })?;
+
+            if self.filename.ends_with(".csv") {
+                content
+                    .lines()
+                    .filter(|line| !line.trim().is_empty() && !line.starts_with("collector,"))
+                    .map(ManifestEntry::from_csv)
+                    .collect()
+            } else {
+                serde_json::from_str(&content).map_err(|e| {
+                    ParseError::SourceListing(format!("Invalid manifest {}: {}", self.filename, e))
+                })
+            }
+        }
+    }
+
+    impl RibSource for ManifestSource {
+        fn get_name(&self) -> &str {
+            "manifest"
+        }
+
+        fn get_skip_list(&self) -> &Vec<String> {
+            &self.skip_list
+        }
+
+        fn get_rib_list(
+            &self,
+            date: &Ymd,
+            hours: &[u32],
+            dir: &str,
+        ) -> Result<Vec<RibFile>, ParseError> {
+            let timestamps: Vec<u64> = hours.iter().map(|h| date.to_unix_ts_at(*h)).collect();
+            let rib_files: Vec<RibFile> = self
+                .read_entries()?
+                .iter()
+                .filter(|entry| timestamps.contains(&(entry.timestamp - entry.timestamp % 3600)))
+                .map(|entry| entry.to_rib_file(dir))
+                .collect();
+
+            debug!(
+                "Found {} MRT files for date {} in {}",
+                rib_files.len(),
+                date,
+                self.filename
+            );
+            Ok(rib_files)
+        }
+    }
+
+    /// A local directory tree of RIB files, e.g. a mirror of an MRT archive.
+    /// Files are parsed in place, and the first directory below the root is used as
+    /// the collector name.
+    pub struct LocalDirSource {
+        root: String,
+        file_regex: String,
+        skip_list: Vec<String>,
+    }
+
+    impl LocalDirSource {
+        pub fn new(root: String, file_regex: Option<String>, skip_list: Vec<String>) -> Self {
+            Self {
+                root,
+                file_regex: file_regex.unwrap_or(String::from(
+                    r"(rib|bview)\.{yyyy}{mm}{dd}\.{hh}00|rib\.{ts}\.",
+                )),
+                skip_list,
+            }
+        }
+
+        fn walk(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), ParseError> {
+            let entries = fs::read_dir(path).map_err(|e| {
+                ParseError::SourceListing(format!("Unable to list {:?}: {}", path, e))
+            })?;
+            for entry in entries.flatten() {
+                let entry_path = entry.path();
+                if entry_path.is_dir() {
+                    Self::walk(&entry_path, files)?;
+                } else {
+                    files.push(entry_path);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl RibSource for LocalDirSource {
+        fn get_name(&self) -> &str {
+            "local-dir"
+        }
+
+        fn get_skip_list(&self) -> &Vec<String> {
+            &self.skip_list
+        }
+
+        fn get_rib_list(
+            &self,
+            date: &Ymd,
+            hours: &[u32],
+            _dir: &str,
+        ) -> Result<Vec<RibFile>, ParseError> {
+            let root = PathBuf::from(date.fill_template(&self.root, 0));
+            let file_regexes = compile_file_regexes(&self.file_regex, date, hours)?;
+            let mut files = Vec::<PathBuf>::new();
+            Self::walk(&root, &mut files)?;
+
+            let rib_files: Vec<RibFile> = files
+                .iter()
+                .filter_map(|path| {
+                    let basename = path.file_name()?.to_str()?;
+                    let timestamp = match_file_regexes(&file_regexes, basename)?;
+                    let relative = path.strip_prefix(&root).ok()?;
+                    let collector = if relative.components().count() > 1 {
+                        relative
+                            .components()
+                            .next()?
+                            .as_os_str()
+                            .to_str()?
+                            .to_owned()
+                    } else {
+                        get_collector_name(basename)
+                    };
+                    let path = path.to_str()?.to_owned();
+                    Some(
+                        RibFile::new(path.clone(), path)
+                            .with_collector(Some(collector))
+                            .with_timestamp(Some(timestamp)),
+                    )
+                })
+                .collect();
+
+            debug!("Found {} MRT files in {:?}", rib_files.len(), root);
+            Ok(rib_files)
+        }
+    }
+
+    fn is_local_path(url: &str) -> bool {
+        !url.contains("://")
+    }
+
+    fn with_trailing_slash(url: String) -> String {
+        if url.ends_with('/') {
+            url
+        } else {
+            format!("{}/", url)
+        }
+    }
+
+    /// Fetch the MD5 checksum of a file, if "<file>.md5" is in the same directory listing
+    fn get_md5(dir_url: &str, link: &str, links: &[String]) -> Option<String> {
+        let md5_link = format!("{}.md5", link);
+        if !links.contains(&md5_link) {
+            return None;
+        }
+        get_text(&format!("{}{}", dir_url, md5_link))
+            .inspect_err(|e| debug!("No MD5 checksum for {}: {}", link, e))
+            .ok()
+            .and_then(|text| {
+                // Both "<md5>  <file>" and "MD5 (<file>) = <md5>" formats are common
+                text.split_whitespace()
+                    .find(|word| word.len() == 32 && word.chars().all(|c| c.is_ascii_hexdigit()))
+                    .map(str::to_owned)
+            })
+    }
+
+    /// Build a regex matching files for each of the chosen hours, keyed by the
+    /// timestamp of that hour
+    fn compile_file_regexes(
+        file_regex: &str,
+        date: &Ymd,
+        hours: &[u32],
+    ) -> Result<Vec<(u64, Regex)>, ParseError> {
+        hours
+            .iter()
+            .map(|hour| {
+                Regex::new(&date.fill_template(file_regex, *hour))
+                    .map(|regex| (date.to_unix_ts_at(*hour), regex))
+                    .map_err(|e| ParseError::SourceListing(format!("Invalid file regex: {}", e)))
+            })
+            .collect()
+    }
+
+    /// Return the timestamp of the first hour whose regex matches a filename.
+    /// Checksum files are never matched.
+    fn match_file_regexes(file_regexes: &[(u64, Regex)], filename: &str) -> Option<u64> {
+        if filename.ends_with(".md5") {
+            return None;
+        }
+        file_regexes
+            .iter()
+            .find(|(_, regex)| regex.is_match(filename))
+            .map(|(timestamp, _)| *timestamp)
+    }
+
+    /// Build the RIB source selected on the CLI
+    pub fn from_args(args: &DownloadArgs) -> Result<Box<dyn RibSource>, ParseError> {
+        let url = args.source_url.clone();
+        let file_regex = args.file_regex.clone();
+        let skip_list = args.skip.clone();
+
+        Ok(match args.source {
+            MrtSource::Broker => Box::new(BrokerSource::new(skip_list)),
+            MrtSource::Bgproutes => {
+                Box::new(CollectorDirSource::bgproutes(url, file_regex, skip_list))
+            }
+            MrtSource::Pch => Box::new(CollectorDirSource::pch(url, file_regex, skip_list)),
+            MrtSource::HttpDir => Box::new(HttpDirSource::new(
+                url.ok_or(ParseError::SourceListing(String::from(
+                    "--source-url is required for the http-dir source",
+                )))?,
+                file_regex,
+                skip_list,
+            )),
+            MrtSource::Manifest => Box::new(ManifestSource::new(
+                url.ok_or(ParseError::SourceListing(String::from(
+                    "--source-url is required for the manifest source",
+                )))?,
+                skip_list,
+            )),
+            MrtSource::LocalDir => Box::new(LocalDirSource::new(
+                url.ok_or(ParseError::SourceListing(String::from(
+                    "--source-url is required for the local-dir source",
+                )))?,
+                file_regex,
+                skip_list,
+            )),
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::file::TempFile;
+
+        // 2026-02-04 00:00 UTC
+        const MIDNIGHT: u64 = 1770163200;
+
+        /// Write a manifest to a temporary file and list the RIB files in it for 2026-02-04
+        fn list_manifest(
+            name: &str,
+            content: &str,
+            hours: &[u32],
+        ) -> Result<Vec<RibFile>, ParseError> {
+            let manifest = TempFile::new(name, content);
+            let source = ManifestSource::new(manifest.path().to_owned(), Vec::new());
+            source.get_rib_list(&Ymd::new(2026, 2, 4).unwrap(), hours, "./mrts")
+        }
+
+        #[test]
+        fn parses_csv_lines() {
+            let entry = ManifestEntry::from_csv(&format!(
+                "ris.rrc00, {}, https://data.ris.ripe.net/rrc00/bview.gz, ris.rrc00.bview.gz, 1234, abcd",
+                MIDNIGHT
+            ))
+            .unwrap();
+            assert_eq!(entry.collector, "ris.rrc00");
+            assert_eq!(entry.timestamp, MIDNIGHT);
+            assert_eq!(entry.url, "https://data.ris.ripe.net/rrc00/bview.gz");
+            assert_eq!(entry.filename, "ris.rrc00.bview.gz");
+            assert_eq!(entry.size, Some(1234));
+            assert_eq!(entry.md5.as_deref(), Some("abcd"));
+        }
+
+        #[test]
+        fn csv_size_and_md5_are_optional() {
+            let entry =
+                ManifestEntry::from_csv("rrc00,1770163200,/mirror/bview.gz,bview.gz").unwrap();
+            assert_eq!((entry.size, entry.md5), (None, None));
+
+            let entry = ManifestEntry::from_csv("rrc00,1770163200,/mirror/bview.gz,bview.gz,,abcd")
+                .unwrap();
+            assert_eq!(entry.size, None);
+            assert_eq!(entry.md5.as_deref(), Some("abcd"));
+        }
+
+        #[test]
+        fn rejects_invalid_csv_lines() {
+            for line in [
+                "rrc00,1770163200,/mirror/bview.gz",
+                "rrc00",
+                "rrc00,midnight,/mirror/bview.gz,bview.gz",
+                "rrc00,1770163200,/mirror/bview.gz,bview.gz,big",
+            ] {
+                assert!(
+                    matches!(
+                        ManifestEntry::from_csv(line),
+                        Err(ParseError::SourceListing(_))
+                    ),
+                    "{}",
+                    line
+                );
+            }
+        }
+
+        #[test]
+        fn lists_csv_manifest_entries_for_the_selected_hours() {
+            let rib_files = list_manifest(
+                "hours.csv",
+                "collector,timestamp,url,filename,size,md5\n\
+                 rrc00,1770163200,https://example.com/a.gz,a.gz\n\
+                 rrc00,1770192000,https://example.com/b.gz,b.gz\n\
+                 \n\
+                 rrc00,1770249600,https://example.com/c.gz,c.gz\n",
+                &[0],
+            )
+            .unwrap();
+            assert_eq!(rib_files.len(), 1);
+            assert_eq!(rib_files[0].url, "https://example.com/a.gz");
+            assert_eq!(rib_files[0].filename, "./mrts/a.gz");
+            assert_eq!(rib_files[0].get_collector(), "rrc00");
+            assert!(!rib_files[0].is_local());
+
+            let rib_files = list_manifest(
+                "all-hours.csv",
+                "rrc00,1770163200,https://example.com/a.gz,a.gz\n\
+                 rrc00,1770192000,https://example.com/b.gz,b.gz\n",
+                &(0..24).collect::<Vec<u32>>(),
+            )
+            .unwrap();
+            assert_eq!(rib_files.len(), 2);
+        }
+
+        #[test]
+        fn lists_json_manifest_entries() {
+            let rib_files = list_manifest(
+                "entries.json",
+                r#"[
+                    {"collector": "rrc00", "timestamp": 1770163200, "url": "/mirror/rrc00/a.gz",
+                     "filename": "a.gz", "size": 1234, "md5": "abcd"},
+                    {"collector": "rrc01", "timestamp": 1770163260, "url": "https://example.com/b.gz",
+                     "filename": "b.gz"}
+                ]"#,
+                &[0],
+            )
+            .unwrap();
+            assert_eq!(rib_files.len(), 2);
+
+            // Local files are parsed in place, rather than downloaded to the RIBs path
+            assert_eq!(rib_files[0].filename, "/mirror/rrc00/a.gz");
+            assert!(rib_files[0].is_local());
+            assert_eq!(rib_files[0].size, Some(1234));
+            assert_eq!(rib_files[0].md5.as_deref(), Some("abcd"));
+
+            assert_eq!(rib_files[1].filename, "./mrts/b.gz");
+            assert_eq!(rib_files[1].timestamp, Some(1770163260));
+            assert_eq!(
+                (rib_files[1].size, rib_files[1].md5.as_deref()),
+                (None, None)
+            );
+        }
+
+        #[test]
+        fn rejects_json_manifests_missing_fields() {
+            let result = list_manifest(
+                "missing-url.json",
+                r#"[{"collector": "rrc00", "timestamp": 1770163200, "filename": "a.gz"}]"#,
+                &[0],
+            );
+            assert!(matches!(result, Err(ParseError::SourceListing(e)) if e.contains("url")));
+        }
+
+        #[test]
+        fn pch_files_match_only_their_own_hour() {
+            let source = CollectorDirSource::pch(None, None, Vec::new());
+            let file_regexes =
+                compile_file_regexes(&source.file_regex, &Ymd::new(2026, 2, 4).unwrap(), &[0, 8])
+                    .unwrap();
+            let matched = |filename: &str| {
+                match_file_regexes(&file_regexes, filename).map(|ts| ts - MIDNIGHT)
+            };
+            assert_eq!(
+                matched("route-collector.ams.pch.net-mrt-bgp-rib-2026.02.04-00.00.gz"),
+                Some(0)
+            );
+            assert_eq!(
+                matched("route-collector.ams.pch.net-mrt-bgp-rib-2026.02.04-08.00.gz"),
+                Some(8 * 3600)
+            );
+            assert_eq!(
+                matched("route-collector.ams.pch.net-mrt-bgp-rib-2026.02.04-16.00.gz"),
+                None
+            );
+            assert_eq!(
+                matched("route-collector.ams.pch.net-mrt-bgp-rib-2026.02.04-08.00.gz.md5"),
+                None
+            );
+        }
+
+        #[test]
+        fn missing_manifest_is_an_error() {
+            let source =
+                ManifestSource::new(String::from("/nonexistent/manifest.json"), Vec::new());
+            let result = source.get_rib_list(&Ymd::new(2026, 2, 4).unwrap(), &[0], "./mrts");
+            assert!(matches!(result, Err(ParseError::SourceListing(_))));
+        }
+    }
+}
@@ -1,8 +1,11 @@
 pub mod rib_getter {
-    use crate::file::ensure_dir;
-    use crate::http::http_client::download_file;
-    use bgpkit_broker::BgpkitBroker;
-    use log::{debug, info};
+    use crate::args::cli_args::{DownloadArgs, Project};
+    use crate::dates::ymd::Ymd;
+    use crate::file::{ensure_dir, get_collector_name, get_source_name};
+    use crate::http::http_client::{download_file, verify_file};
+    use crate::parse_errors::parse_error::ParseError;
+    use crate::rib_sources::rib_source::RibSource;
+    use log::{info, warn};
     use rayon::iter::{IntoParallelIterator, ParallelIterator};
     use std::path::Path;
 
@@ -10,60 +13,171 @@ pub mod rib_getter {
     pub struct RibFile {
         pub url: String,
         pub filename: String,
+        // Expected file size and MD5 checksum, if the source publishes them
+        pub size: Option<u64>,
+        pub md5: Option<String>,
+        // Set when the collector name can't be derived from the filename
+        pub collector: Option<String>,
+        // Unix timestamp of the RIB dump, if known before parsing
+        pub timestamp: Option<u64>,
     }
 
     impl RibFile {
         pub fn new(url: String, filename: String) -> Self {
-            Self { url, filename }
+            Self {
+                url,
+                filename,
+                size: None,
+                md5: None,
+                collector: None,
+                timestamp: None,
+            }
+        }
+
+        pub fn with_size(mut self, size: Option<u64>) -> Self {
+            self.size = size;
+            self
+        }
+
+        pub fn with_md5(mut self, md5: Option<String>) -> Self {
+            self.md5 = md5;
+            self
+        }
+
+        pub fn with_collector(mut self, collector: Option<String>) -> Self {
+            self.collector = collector;
+            self
         }
+
+        pub fn with_timestamp(mut self, timestamp: Option<u64>) -> Self {
+            self.timestamp = timestamp;
+            self
+        }
+
         pub fn get_filename(&self) -> &String {
             &self.filename
         }
+
+        /// Whether the file is parsed in place from a local mirror, rather than downloaded
+        pub fn is_local(&self) -> bool {
+            self.url == self.filename
+        }
+
+        pub fn get_collector(&self) -> String {
+            self.collector
+                .clone()
+                .unwrap_or_else(|| get_collector_name(&self.filename))
+        }
     }
 
-    /// Download all the ribs files for a specific day
-    pub fn download_ribs_for_day(date: &str, dir: &str) -> Vec<RibFile> {
-        info!("Downloading MRT RIBs for {}", date);
-        let rib_files = get_rib_list_for_day(date, dir);
-        download_ribs_to_dir(&rib_files);
-        rib_files
+    /// Which collectors to download from, as chosen on the CLI
+    #[derive(Debug, Default)]
+    pub struct RibFilter {
+        collectors: Vec<String>,
+        exclude_collectors: Vec<String>,
+        projects: Vec<Project>,
     }
 
-    fn download_ribs_to_dir(rib_files: &Vec<RibFile>) {
-        ensure_dir(rib_files[0].get_filename());
-        rib_files
-            .into_par_iter()
-            .for_each(|rib_file| download_file(&rib_file.url, Path::new(&rib_file.filename)));
+    impl RibFilter {
+        pub fn from_args(args: &DownloadArgs) -> Self {
+            Self {
+                collectors: args.collectors.clone(),
+                exclude_collectors: args.exclude_collectors.clone(),
+                projects: args.project.clone(),
+            }
+        }
+
+        /// Collectors can be given with or without their project prefix,
+        /// e.g. "rrc00" matches "ris.rrc00"
+        fn has_collector(collectors: &[String], collector: &str) -> bool {
+            collectors
+                .iter()
+                .any(|c| collector == c || collector.ends_with(&format!(".{}", c)))
+        }
+
+        pub fn matches(&self, rib_file: &RibFile) -> bool {
+            let collector = rib_file.get_collector();
+            (self.collectors.is_empty() || Self::has_collector(&self.collectors, &collector))
+                && !Self::has_collector(&self.exclude_collectors, &collector)
+                && (self.projects.is_empty()
+                    || self
+                        .projects
+                        .iter()
+                        .any(|p| p.get_source_name() == get_source_name(&collector)))
+        }
     }
 
-    /// Return a list of available RIBs for a specific day (with details like download URL)
-    fn get_rib_list_for_day(date: &str, dir: &str) -> Vec<RibFile> {
-        let broker = BgpkitBroker::new().ts_start(date).ts_end(date);
-        let ribs = broker.daily_ribs().unwrap();
-        debug!("Found {} MRT files for date {}", ribs.len(), date);
-
-        let mut rib_files = Vec::<RibFile>::new();
-        for rib in ribs {
-            let basename = Path::new(&rib.url).file_name().unwrap().to_str().unwrap();
-
-            let source = if rib.collector_id.starts_with("rrc") {
-                String::from("ris")
-            } else {
-                String::from("route-views")
-            };
-
-            let filename = if rib.collector_id.starts_with(&source) {
-                format!("{}/{}.{}", dir, rib.collector_id, basename)
-            } else {
-                format!("{}/{}.{}.{}", dir, &source, rib.collector_id, basename)
-            };
-
-            rib_files.push(RibFile {
-                url: rib.url,
-                filename,
-            });
+    /// List the ribs files for specific hours of a day from a RIB source, without downloading them
+    pub fn get_ribs_for_day(
+        source: &dyn RibSource,
+        filter: &RibFilter,
+        date: &Ymd,
+        hours: &[u32],
+        dir: &str,
+    ) -> Result<Vec<RibFile>, ParseError> {
+        info!("Listing MRT RIBs for {} from {}", date, source.get_name());
+        Ok(source
+            .get_rib_list(date, hours, dir)?
+            .into_iter()
+            .filter(|rib_file| !source.is_skipped(&rib_file.url) && filter.matches(rib_file))
+            .collect())
+    }
+
+    /// Log the RIB files which would be downloaded, and their total size where known
+    pub fn log_dry_run(rib_files: &[RibFile]) {
+        for rib_file in rib_files {
+            match rib_file.size {
+                Some(size) => info!("{} -> {} ({} bytes)", rib_file.url, rib_file.filename, size),
+                None => info!("{} -> {}", rib_file.url, rib_file.filename),
+            }
         }
 
-        rib_files
+        let total: u64 = rib_files.iter().filter_map(|r| r.size).sum();
+        let unknown = rib_files.iter().filter(|r| r.size.is_none()).count();
+        info!(
+            "Would download {} RIB files, {:.2} GB ({} files of unknown size)",
+            rib_files.len(),
+            total as f64 / 1_000_000_000.0,
+            unknown
+        );
+    }
+
+    /// Download RIB files, returning only the files which were successfully downloaded.
+    /// Files which fail to download are skipped, unless in strict mode. Local files are
+    /// only verified, they are never downloaded over or removed.
+    pub fn download_ribs(rib_files: Vec<RibFile>, strict: bool) -> Vec<RibFile> {
+        if let Some(rib_file) = rib_files.iter().find(|rib_file| !rib_file.is_local()) {
+            ensure_dir(rib_file.get_filename());
+        }
+
+        let total = rib_files.len();
+        let downloaded: Vec<RibFile> = rib_files
+            .into_par_iter()
+            .filter(|rib_file| {
+                let path = Path::new(&rib_file.filename);
+                let result = if rib_file.is_local() {
+                    verify_file(path, rib_file.size, rib_file.md5.as_deref())
+                } else {
+                    download_file(&rib_file.url, path, rib_file.size, rib_file.md5.as_deref())
+                };
+                match result {
+                    Ok(()) => true,
+                    Err(e) if strict => panic!("{}", e),
+                    Err(e) => {
+                        warn!("Skipping {}: {}", rib_file.filename, e);
+                        false
+                    }
+                }
+            })
+            .collect();
+
+        if downloaded.len() != total {
+            warn!(
+                "Failed to download or verify {} of {} RIB files",
+                total - downloaded.len(),
+                total
+            );
+        }
+        downloaded
     }
 }
@@ -0,0 +1,513 @@
+pub mod rpsl_import {
+    use crate::comm_matchers::community_matchers::CommunityMatcher;
+    use crate::file::ensure_dir;
+    use crate::parse_errors::parse_error::ParseError;
+    use crate::peer_attrs::peer_data::{PeerLocation, PeerType};
+    use log::info;
+    use std::fmt::Write as _;
+    use std::fs;
+
+    // Wording for how a route was learned, checked in order so that e.g. "non-customer"
+    // isn't taken as "customer"
+    const PEER_TYPE_WORDS: [(&str, PeerType); 11] = [
+        ("non-customer", PeerType::Peer),
+        ("paid peer", PeerType::PaidPeer),
+        ("customer", PeerType::Customer),
+        ("downstream", PeerType::Customer),
+        ("peering", PeerType::Peer),
+        ("peer", PeerType::Peer),
+        ("upstream", PeerType::Upstream),
+        ("transit provider", PeerType::Upstream),
+        ("transit", PeerType::Upstream),
+        ("provider", PeerType::Upstream),
+        ("supplier", PeerType::Upstream),
+    ];
+
+    // Wording for where a route was learned, matched case insensitively
+    const LOCATION_WORDS: [(&str, &str); 16] = [
+        ("east coast", "NorthAmerica/UsEastCoast"),
+        ("west coast", "NorthAmerica/UsWestCoast"),
+        ("north america", "NorthAmerica"),
+        ("north-america", "NorthAmerica"),
+        ("united states", "NorthAmerica//US"),
+        ("south america", "SouthAmerica"),
+        ("south-america", "SouthAmerica"),
+        ("latin america", "SouthAmerica"),
+        ("europe", "Europe"),
+        ("asia pacific", "AsiaPac"),
+        ("asia", "AsiaPac"),
+        ("australia", "AsiaPac/Oceania/AU"),
+        ("oceania", "AsiaPac/Oceania"),
+        ("india", "AsiaPac/SouthAsia/IN"),
+        ("middle east", "MiddleEast"),
+        ("africa", "Africa"),
+    ];
+
+    // Abbreviations for where a route was learned, matched case sensitively as whole words.
+    // Ambiguous abbreviations are left out, e.g. "NA" (also "not applicable") and "SA"
+    // (also South Africa or Saudi Arabia).
+    const LOCATION_ABBREVIATIONS: [(&str, &str); 4] = [
+        ("US", "NorthAmerica//US"),
+        ("USA", "NorthAmerica//US"),
+        ("EU", "Europe"),
+        ("APAC", "AsiaPac"),
+    ];
+
+    // Wording of action communities, which customers set to control their routes,
+    // rather than informational communities
+    const ACTION_WORDS: [&str; 9] = [
+        "prepend",
+        "do not",
+        "don't",
+        "announce to",
+        "no-export",
+        "blackhole",
+        "local-pref",
+        "localpref",
+        "suppress",
+    ];
+
+    /// Find a whole word, or phrase, in lower case text
+    fn find_word(text: &str, word: &str) -> Option<usize> {
+        text.match_indices(word).map(|(pos, _)| pos).find(|pos| {
+            let pos = *pos;
+            let before = text[..pos].chars().next_back();
+            let after = text[pos + word.len()..].chars().next();
+            // Allow plurals, e.g. "customers", "peers"
+            let after = match after {
+                Some('s') => text[pos + word.len() + 1..].chars().next(),
+                _ => after,
+            };
+            !before.is_some_and(|c| c.is_alphanumeric() || c == '-')
+                && !after.is_some_and(|c| c.is_alphanumeric())
+        })
+    }
+
+    fn has_word(text: &str, word: &str) -> bool {
+        find_word(text, word).is_some()
+    }
+
+    /// The peer types in remark text, in the order they appear
+    fn find_peer_types(text: &str) -> Vec<PeerType> {
+        let mut text = text.to_lowercase();
+        let mut found = Vec::<(usize, PeerType)>::new();
+        for (word, peer_type) in PEER_TYPE_WORDS {
+            while let Some(pos) = find_word(&text, word) {
+                found.push((pos, peer_type.clone()));
+                // Blank out the match, so it isn't matched again by a shorter word
+                text.replace_range(pos..pos + word.len(), &" ".repeat(word.len()));
+            }
+        }
+        found.sort_by_key(|(pos, _)| *pos);
+        found.into_iter().map(|(_, peer_type)| peer_type).collect()
+    }
+
+    /// The most precise location in remark text, None if there is none, or it
+    /// mentions locations which aren't within each other, e.g. "Middle East and Africa"
+    fn find_location(text: &str) -> Option<PeerLocation> {
+        let lower = text.to_lowercase();
+        let words = LOCATION_WORDS
+            .iter()
+            .filter(|(word, _)| has_word(&lower, word));
+        let abbreviations = LOCATION_ABBREVIATIONS.iter().filter(|(abbreviation, _)| {
+            text.split(|c: char| !c.is_alphanumeric())
+                .any(|w| w == *abbreviation)
+        });
+
+        let mut found: Option<&str> = None;
+        for (_, location) in words.chain(abbreviations) {
+            found = match found {
+                None => Some(location),
+                Some(f) if location.starts_with(f) => Some(location),
+                Some(f) if f.starts_with(location) => Some(f),
+                Some(_) => return None,
+            };
+        }
+        found.map(|location| location.parse().unwrap())
+    }
+
+    /// Parse the community matchers in remark text, and the text left without them
+    fn find_communities(text: &str) -> (Vec<CommunityMatcher>, String) {
+        let mut matchers = Vec::<CommunityMatcher>::new();
+        let mut rest = Vec::<&str>::new();
+        for word in text.split_whitespace() {
+            let token = word.trim_matches(|c: char| matches!(c, ',' | ';' | '.' | '(' | ')' | '"'));
+            let is_community = token.split_once(':').is_some_and(|(asn, value)| {
+                !asn.is_empty() && asn.chars().all(|c| c.is_ascii_digit()) && !value.is_empty()
+            });
+            match token.parse::<CommunityMatcher>() {
+                Ok(matcher) if is_community => matchers.push(matcher),
+                _ => rest.push(word),
+            }
+        }
+        (matchers, rest.join(" "))
+    }
+
+    /// A candidate mapping for one community, the remark it was found in and
+    /// the heading of the section the remark is in
+    struct Candidate {
+        matcher: CommunityMatcher,
+        remark: String,
+        heading: Option<String>,
+        peer_type: Option<PeerType>,
+        peer_location: Option<PeerLocation>,
+    }
+
+    /// Candidate community mappings for an aut-num, from its remarks
+    pub struct RpslCandidates {
+        asn: u32,
+        filename: String,
+        candidates: Vec<Candidate>,
+    }
+
+    impl RpslCandidates {
+        /// Read the remarks of an aut-num object from an RPSL dump, e.g. ripe.db.aut-num.gz
+        fn read_remarks(filename: &str, asn: u32) -> Result<Vec<String>, ParseError> {
+            let rpsl_error = |e: String| ParseError::Rpsl(format!("{}: {}", filename, e));
+            let object = format!("as{}", asn);
+            let mut in_object = false;
+            let mut attribute = String::new();
+            let mut remarks = Vec::<String>::new();
+
+            for line in oneio::read_lines(filename).map_err(|e| rpsl_error(e.to_string()))? {
+                let line = line.map_err(|e| rpsl_error(e.to_string()))?;
+                if line.starts_with('%') || line.starts_with('#') {
+                    continue;
+                }
+                if line.trim().is_empty() {
+                    if in_object {
+                        return Ok(remarks);
+                    }
+                    attribute.clear();
+                    continue;
+                }
+
+                // Continuation lines start with whitespace, or a + for an empty line
+                let value = if line.starts_with([' ', '\t', '+']) {
+                    line[1..].trim()
+                } else if let Some((name, value)) = line.split_once(':') {
+                    attribute = name.trim().to_lowercase();
+                    if attribute == "aut-num" && value.trim().to_lowercase() == object {
+                        in_object = true;
+                    }
+                    value.trim()
+                } else {
+                    continue;
+                };
+                if in_object && attribute == "remarks" {
+                    remarks.push(value.to_owned());
+                }
+            }
+
+            if in_object {
+                Ok(remarks)
+            } else {
+                Err(rpsl_error(format!("no aut-num object for AS{}", asn)))
+            }
+        }
+
+        /// Find communities in an aut-num's remarks, and guess their meanings from the
+        /// wording of the remark they're in, or the heading of the table they're in.
+        /// Tables with a column of communities for each peer type, like PCCW's, are
+        /// recognised by a heading which names two or more peer types.
+        pub fn from_file(filename: &str, asn: u32) -> Result<Self, ParseError> {
+            let remarks = Self::read_remarks(filename, asn)?;
+            let mut candidates = Vec::<Candidate>::new();
+
+            // Context from the heading of the current section, reset by an empty remark
+            let mut columns = Vec::<PeerType>::new();
+            let mut section_type: Option<PeerType> = None;
+            let mut section_location: Option<PeerLocation> = None;
+            let mut section_is_action = false;
+            let mut heading: Option<&String> = None;
+
+            for remark in &remarks {
+                let (matchers, text) = find_communities(remark);
+                let lower = text.to_lowercase();
+                let is_action = ACTION_WORDS.iter().any(|word| lower.contains(word));
+                let peer_types = find_peer_types(&text);
+                let peer_location = find_location(&text);
+
+                if text.chars().all(|c| !c.is_alphanumeric()) && matchers.is_empty() {
+                    // An empty remark ends the section, a line of dashes doesn't
+                    if text.is_empty() {
+                        columns.clear();
+                        section_type = None;
+                        section_location = None;
+                        section_is_action = false;
+                        heading = None;
+                    }
+                    continue;
+                }
+
+                if matchers.is_empty() {
+                    section_is_action = is_action;
+                    heading = Some(remark);
+                    if peer_types.len() > 1 {
+                        columns = peer_types;
+                    } else {
+                        section_type = peer_types.into_iter().next().or(section_type);
+                        section_location = peer_location.or(section_location);
+                    }
+                    continue;
+                }
+
+                if is_action || section_is_action {
+                    continue;
+                }
+
+                let in_columns = columns.len() == matchers.len();
+                let remark_type = match peer_types.len() {
+                    1 => peer_types.into_iter().next(),
+                    _ => None,
+                };
+                for (i, matcher) in matchers.into_iter().enumerate() {
+                    let peer_type = if in_columns {
+                        Some(columns[i].clone())
+                    } else {
+                        remark_type.clone().or(section_type.clone())
+                    };
+                    candidates.push(Candidate {
+                        matcher,
+                        remark: remark.clone(),
+                        heading: heading.cloned(),
+                        peer_type,
+                        peer_location: peer_location.clone().or(section_location.clone()),
+                    });
+                }
+            }
+
+            info!(
+                "Found {} communities in {} remarks of AS{} in {}",
+                candidates.len(),
+                remarks.len(),
+                asn,
+                filename
+            );
+            Ok(Self {
+                asn,
+                filename: filename.to_owned(),
+                candidates,
+            })
+        }
+
+        /// Write the candidates as lines which can be reviewed and copied into comm_data.rs
+        pub fn to_file(&self, filename: &String) {
+            let mut out = String::new();
+            writeln!(
+                out,
+                "// Candidate community mappings for AS{}, imported from its aut-num remarks in {}.\n\
+                 // The meanings are guessed from the wording of each remark, and the heading of\n\
+                 // its section, so they need checking before adding to comm_data.rs.",
+                self.asn, self.filename
+            )
+            .unwrap();
+            writeln!(out, "\n/* AS{} */", self.asn).unwrap();
+            writeln!(
+                out,
+                "let mut cm = CommMappings::new(HashMap::new(), HashMap::new());"
+            )
+            .unwrap();
+
+            for candidate in &self.candidates {
+                let matcher = &candidate.matcher;
+                writeln!(out).unwrap();
+                if let Some(heading) = &candidate.heading {
+                    writeln!(out, "// {}", heading).unwrap();
+                }
+                writeln!(out, "// {}", candidate.remark).unwrap();
+                // Mapped under another ASN, check this isn't another network's community
+                let comment = if matcher.get_asn() != self.asn {
+                    writeln!(
+                        out,
+                        "// {} has the ASN field of AS{}",
+                        matcher,
+                        matcher.get_asn()
+                    )
+                    .unwrap();
+                    "// "
+                } else {
+                    ""
+                };
+                if candidate.peer_type.is_none() && candidate.peer_location.is_none() {
+                    writeln!(out, "// {}: no peer type or location found", matcher).unwrap();
+                }
+                if let Some(peer_type) = &candidate.peer_type {
+                    writeln!(
+                        out,
+                        "{}cm.add_peer_type(matcher(\"{}\"), PeerType::{:?});",
+                        comment, matcher, peer_type
+                    )
+                    .unwrap();
+                }
+                if let Some(peer_location) = &candidate.peer_location {
+                    writeln!(
+                        out,
+                        "{}cm.add_peer_location(matcher(\"{}\"), location(\"{}\"));",
+                        comment, matcher, peer_location
+                    )
+                    .unwrap();
+                }
+            }
+
+            writeln!(
+                out,
+                "\nasn_mappings.insert(MrtAsn::from_u32({}), cm);",
+                self.asn
+            )
+            .unwrap();
+
+            ensure_dir(filename);
+            fs::write(filename, out).unwrap();
+            info!("Wrote candidate mappings to {}", filename);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::file::TempFile;
+
+        fn location(text: &str) -> Option<String> {
+            find_location(text).map(|location| location.to_string())
+        }
+
+        #[test]
+        fn finds_whole_words_and_plurals() {
+            assert_eq!(find_word("learned from customers", "customer"), Some(13));
+            assert_eq!(find_word("learned from peers.", "peer"), Some(13));
+            assert_eq!(find_word("peer", "peer"), Some(0));
+            assert!(!has_word("customerset", "customer"));
+            assert!(!has_word("non-customer routes", "customer"));
+            assert!(!has_word("peering", "peer"));
+            assert!(!has_word("peerss", "peer"));
+        }
+
+        #[test]
+        fn finds_peer_types_in_order() {
+            assert_eq!(
+                find_peer_types("Routes learned from Customers"),
+                [PeerType::Customer]
+            );
+            assert_eq!(find_peer_types("Non-customer routes"), [PeerType::Peer]);
+            assert_eq!(
+                find_peer_types("Paid peers, peers and transit providers"),
+                [PeerType::PaidPeer, PeerType::Peer, PeerType::Upstream]
+            );
+            assert_eq!(
+                find_peer_types("Customers  Peers  Upstreams"),
+                [PeerType::Customer, PeerType::Peer, PeerType::Upstream]
+            );
+            assert_eq!(find_peer_types("Private peering"), [PeerType::Peer]);
+            assert!(find_peer_types("Learned in Frankfurt").is_empty());
+        }
+
+        #[test]
+        fn finds_the_most_precise_location() {
+            assert_eq!(location("Learned in Europe"), Some(String::from("Europe")));
+            assert_eq!(
+                location("North America (east coast)"),
+                Some(String::from("NorthAmerica/UsEastCoast"))
+            );
+            assert_eq!(
+                location("Learned in the USA"),
+                Some(String::from("NorthAmerica//US"))
+            );
+            assert_eq!(location("APAC peers"), Some(String::from("AsiaPac")));
+            assert_eq!(location("Middle East and Africa"), None);
+            assert_eq!(location("Learned from peers"), None);
+        }
+
+        #[test]
+        fn ignores_ambiguous_and_lower_case_abbreviations() {
+            assert_eq!(location("Learned in SA"), None);
+            assert_eq!(location("Location: NA"), None);
+            assert_eq!(location("Routes learned from us"), None);
+            assert_eq!(location("Set by eu customers"), None);
+        }
+
+        #[test]
+        fn finds_communities_and_the_rest_of_the_text() {
+            let (matchers, rest) = find_communities("3491:1000, 3491:2xxx (peers) at 10:30");
+            assert_eq!(
+                matchers
+                    .iter()
+                    .map(|m| m.to_string())
+                    .collect::<Vec<String>>(),
+                ["3491:1000", "3491:2xxx", "10:30"]
+            );
+            assert_eq!(rest, "(peers) at");
+
+            let (matchers, rest) = find_communities("See https://example.com:8080/communities");
+            assert!(matchers.is_empty());
+            assert_eq!(rest, "See https://example.com:8080/communities");
+        }
+
+        #[test]
+        fn reads_candidates_from_an_aut_num() {
+            let file = TempFile::new(
+                "aut-num.db",
+                "% Comment\n\
+                 \n\
+                 aut-num:        AS64499\n\
+                 remarks:        64499:1 customer\n\
+                 \n\
+                 aut-num:        AS64500\n\
+                 as-name:        EXAMPLE\n\
+                 remarks:        Informational communities\n\
+                 remarks:        Customers   Peers\n\
+                 remarks:        64500:100   64500:200   Europe\n\
+                 remarks:        64500:3000 routes learned from upstreams\n\
+                 remarks:\n\
+                 remarks:        Learned in North America\n\
+                 +               64500:4000 from peers\n\
+                 remarks:        64500:666 blackhole\n\
+                 remarks:\n\
+                 remarks:        Prepend communities\n\
+                 remarks:        64500:5001 prepend once to peers\n\
+                 \tprepend twice: 64500:5002\n\
+                 mnt-by:         EXAMPLE-MNT\n\
+                 \n\
+                 aut-num:        AS64501\n\
+                 remarks:        64501:1 peer\n",
+            );
+            let candidates = RpslCandidates::from_file(file.path(), 64500);
+            let missing = RpslCandidates::from_file(file.path(), 64502);
+
+            let candidates: Vec<(String, Option<PeerType>, Option<String>)> = candidates
+                .unwrap()
+                .candidates
+                .into_iter()
+                .map(|c| {
+                    (
+                        c.matcher.to_string(),
+                        c.peer_type,
+                        c.peer_location.map(|l| l.to_string()),
+                    )
+                })
+                .collect();
+            assert_eq!(
+                candidates,
+                [
+                    (
+                        String::from("64500:100"),
+                        Some(PeerType::Customer),
+                        Some(String::from("Europe"))
+                    ),
+                    (
+                        String::from("64500:200"),
+                        Some(PeerType::Peer),
+                        Some(String::from("Europe"))
+                    ),
+                    (String::from("64500:3000"), Some(PeerType::Upstream), None),
+                    (
+                        String::from("64500:4000"),
+                        Some(PeerType::Peer),
+                        Some(String::from("NorthAmerica"))
+                    ),
+                ]
+            );
+            assert!(matches!(missing, Err(ParseError::Rpsl(_))));
+        }
+    }
+}
@@ -0,0 +1,144 @@
+pub mod parse_stats {
+    use crate::file::ensure_dir;
+    use log::info;
+    use serde::ser::SerializeStruct;
+    use serde::{Serialize, Serializer};
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::io::BufWriter;
+    use std::time::Duration;
+
+    /// Counters for a single RIB file, or summed over several files
+    #[derive(Debug, Default, Serialize)]
+    pub struct FileStats {
+        records_read: u64,
+        rib_entries: u64,
+        t1_adjacencies: u64,
+        classified: u64,
+        none_found: u64,
+        skipped_default_route: u64,
+        skipped_skip_asn: u64,
+        skipped_empty_path: u64,
+        errors: u64,
+        // Time spent parsing the file. Files are parsed in parallel, so summed over several
+        // files this is more than the wall time of the run.
+        file_time_secs: f64,
+    }
+
+    impl FileStats {
+        pub fn add_record(&mut self) {
+            self.records_read += 1;
+        }
+
+        pub fn add_rib_entry(&mut self) {
+            self.rib_entries += 1;
+        }
+
+        /// Count a T1-T1 adjacency, and whether communities classified the peer type
+        pub fn add_t1_adjacency(&mut self, classified: bool) {
+            self.t1_adjacencies += 1;
+            if classified {
+                self.classified += 1;
+            } else {
+                self.none_found += 1;
+            }
+        }
+
+        pub fn add_skipped_default_route(&mut self) {
+            self.skipped_default_route += 1;
+        }
+
+        pub fn add_skipped_skip_asn(&mut self) {
+            self.skipped_skip_asn += 1;
+        }
+
+        pub fn add_skipped_empty_path(&mut self) {
+            self.skipped_empty_path += 1;
+        }
+
+        pub fn set_errors(&mut self, errors: u64) {
+            self.errors = errors;
+        }
+
+        pub fn set_file_time(&mut self, file_time: Duration) {
+            self.file_time_secs = file_time.as_secs_f64();
+        }
+
+        /// Add the counters from another file into this one
+        pub fn add(&mut self, other: &FileStats) {
+            self.records_read += other.records_read;
+            self.rib_entries += other.rib_entries;
+            self.t1_adjacencies += other.t1_adjacencies;
+            self.classified += other.classified;
+            self.none_found += other.none_found;
+            self.skipped_default_route += other.skipped_default_route;
+            self.skipped_skip_asn += other.skipped_skip_asn;
+            self.skipped_empty_path += other.skipped_empty_path;
+            self.errors += other.errors;
+            self.file_time_secs += other.file_time_secs;
+        }
+    }
+
+    /// Stats for every parsed RIB file, keyed by filename
+    #[derive(Debug, Default)]
+    pub struct RunStats {
+        file_stats: HashMap<String, FileStats>,
+        // Route collector of each file
+        collectors: HashMap<String, String>,
+        // Elapsed time of the whole run
+        wall_time: Duration,
+    }
+
+    impl Serialize for RunStats {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let mut state = serializer.serialize_struct("RunStats", 4)?;
+            state.serialize_field("wall_time_secs", &self.wall_time.as_secs_f64())?;
+            state.serialize_field("total", &self.get_total())?;
+            state.serialize_field("collectors", &self.get_collector_stats())?;
+            state.serialize_field("files", &self.file_stats)?;
+            state.end()
+        }
+    }
+
+    impl RunStats {
+        pub fn add_file_stats(&mut self, filename: &str, collector: &str, file_stats: FileStats) {
+            self.file_stats.insert(filename.to_owned(), file_stats);
+            self.collectors
+                .insert(filename.to_owned(), collector.to_owned());
+        }
+
+        pub fn set_wall_time(&mut self, wall_time: Duration) {
+            self.wall_time = wall_time;
+        }
+
+        /// Sum the stats of all files from the same route collector
+        pub fn get_collector_stats(&self) -> HashMap<String, FileStats> {
+            let mut collector_stats = HashMap::<String, FileStats>::new();
+            for (filename, file_stats) in &self.file_stats {
+                collector_stats
+                    .entry(self.collectors[filename].clone())
+                    .or_default()
+                    .add(file_stats);
+            }
+            collector_stats
+        }
+
+        pub fn get_total(&self) -> FileStats {
+            let mut total = FileStats::default();
+            for file_stats in self.file_stats.values() {
+                total.add(file_stats);
+            }
+            total
+        }
+
+        pub fn to_file(&self, filename: &String) {
+            ensure_dir(filename);
+            let writer = BufWriter::new(File::create(filename).unwrap());
+            serde_json::to_writer_pretty(writer, self).unwrap();
+            info!("Wrote JSON to {}", filename);
+        }
+    }
+}
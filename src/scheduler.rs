@@ -0,0 +1,117 @@
+pub mod file_scheduler {
+    use crate::ribs::rib_getter::RibFile;
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Hands out RIB files to parsing threads, largest first, so that the largest
+    /// files aren't left running on their own once everything else has finished.
+    /// Threads which find no more files to parse are counted as idle, so that
+    /// the threads still parsing a file can hand some of its records to them.
+    pub struct FileScheduler<'a> {
+        files: Vec<(&'a RibFile, Option<u64>)>,
+        next_file: AtomicUsize,
+        idle_threads: AtomicUsize,
+    }
+
+    impl<'a> FileScheduler<'a> {
+        pub fn new(rib_files: &'a [RibFile], stream: bool) -> Self {
+            let mut files: Vec<(&RibFile, Option<u64>)> = rib_files
+                .iter()
+                .map(|rib_file| (rib_file, Self::get_file_size(rib_file, stream)))
+                .collect();
+            // Files of unknown size are parsed last
+            files.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+            Self {
+                files,
+                next_file: AtomicUsize::new(0),
+                idle_threads: AtomicUsize::new(0),
+            }
+        }
+
+        /// The size of the file on disk, or the size published by the RIB source when streaming
+        fn get_file_size(rib_file: &RibFile, stream: bool) -> Option<u64> {
+            if stream {
+                rib_file.size
+            } else {
+                fs::metadata(rib_file.get_filename())
+                    .map(|metadata| metadata.len())
+                    .ok()
+            }
+        }
+
+        pub fn get_total_size(&self) -> u64 {
+            self.files.iter().filter_map(|(_, size)| *size).sum()
+        }
+
+        /// Return the next largest file to parse, and its size if known
+        pub fn next_file(&self) -> Option<(&'a RibFile, Option<u64>)> {
+            let next = self.next_file.fetch_add(1, Ordering::Relaxed);
+            self.files.get(next).copied()
+        }
+
+        /// Record that a thread has no more files to parse
+        pub fn set_idle(&self) {
+            self.idle_threads.fetch_add(1, Ordering::Relaxed);
+        }
+
+        pub fn get_idle_threads(&self) -> usize {
+            self.idle_threads.load(Ordering::Relaxed)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::iter;
+
+        fn rib_file(name: &str, size: Option<u64>) -> RibFile {
+            RibFile::new(format!("https://example.com/{}", name), name.to_owned()).with_size(size)
+        }
+
+        #[test]
+        fn hands_out_the_largest_files_first() {
+            let rib_files = [
+                rib_file("small", Some(10)),
+                rib_file("unknown", None),
+                rib_file("large", Some(1000)),
+                rib_file("medium", Some(100)),
+            ];
+            let scheduler = FileScheduler::new(&rib_files, true);
+            assert_eq!(scheduler.get_total_size(), 1110);
+
+            let order: Vec<(&str, Option<u64>)> = iter::from_fn(|| scheduler.next_file())
+                .map(|(rib_file, size)| (rib_file.get_filename().as_str(), size))
+                .collect();
+            // Files of unknown size are parsed last
+            assert_eq!(
+                order,
+                [
+                    ("large", Some(1000)),
+                    ("medium", Some(100)),
+                    ("small", Some(10)),
+                    ("unknown", None)
+                ]
+            );
+            assert!(scheduler.next_file().is_none());
+        }
+
+        #[test]
+        fn uses_the_size_on_disk_unless_streaming() {
+            let size = fs::metadata("Cargo.toml").unwrap().len();
+            let rib_files =
+                [RibFile::new(String::new(), String::from("Cargo.toml")).with_size(Some(1))];
+            assert_eq!(FileScheduler::new(&rib_files, false).get_total_size(), size);
+            assert_eq!(FileScheduler::new(&rib_files, true).get_total_size(), 1);
+        }
+
+        #[test]
+        fn counts_idle_threads() {
+            let scheduler = FileScheduler::new(&[], false);
+            assert!(scheduler.next_file().is_none());
+            scheduler.set_idle();
+            scheduler.set_idle();
+            assert_eq!(scheduler.get_idle_threads(), 2);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+pub mod snapshot_comparison {
+    use crate::args::cli_args::LocationLevel;
+    use crate::dates::ymd::Ymd;
+    use crate::file::ensure_dir;
+    use crate::mrt_route::route::Route;
+    use crate::vantage_points::vantage_point_ranking::PeeringKey;
+    use log::info;
+    use serde::Serialize;
+    use std::collections::{BTreeMap, HashMap, HashSet};
+    use std::fs::File;
+    use std::io::BufWriter;
+
+    /// Peerings found across all collectors in a single RIB snapshot
+    #[derive(Debug, Default, Serialize)]
+    pub struct SnapshotSummary {
+        collectors: usize,
+        peerings: usize,
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct SnapshotReport {
+        // Peerings found in any snapshot
+        total_peerings: usize,
+        // Peerings which a collector saw in some of its snapshots but not others
+        intermittent_peerings: usize,
+        snapshots: BTreeMap<String, SnapshotSummary>,
+        // For each intermittent peering, the snapshots each collector saw it in
+        intermittent: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    }
+
+    /// The classified peerings which each collector saw in each RIB snapshot
+    #[derive(Debug, Default)]
+    pub struct Snapshots {
+        evidence: HashMap<String, HashMap<u64, HashSet<PeeringKey>>>,
+    }
+
+    impl Snapshots {
+        /// Record a route from an MRT record with the given timestamp. RIB records are
+        /// stamped with the time of the dump, so the timestamp is rounded down to the hour.
+        pub fn add_route(&mut self, collector: &str, timestamp: u32, route: &Route) {
+            let snapshot = timestamp as u64 - timestamp as u64 % 3600;
+            self.evidence
+                .entry(collector.to_owned())
+                .or_default()
+                .entry(snapshot)
+                .or_default()
+                .insert(PeeringKey::from(route));
+        }
+
+        /// Merge the evidence seen in another file into this one
+        pub fn merge(&mut self, other: Snapshots) {
+            for (collector, snapshots) in other.evidence {
+                let collector_snapshots = self.evidence.entry(collector).or_default();
+                for (snapshot, peerings) in snapshots {
+                    collector_snapshots
+                        .entry(snapshot)
+                        .or_default()
+                        .extend(peerings);
+                }
+            }
+        }
+
+        /// Merge the peerings which are the same at the given location level
+        pub fn roll_up(&self, level: LocationLevel) -> Self {
+            Self {
+                evidence: self
+                    .evidence
+                    .iter()
+                    .map(|(collector, snapshots)| {
+                        let snapshots = snapshots
+                            .iter()
+                            .map(|(snapshot, peerings)| {
+                                (
+                                    *snapshot,
+                                    peerings.iter().map(|p| p.roll_up(level)).collect(),
+                                )
+                            })
+                            .collect();
+                        (collector.clone(), snapshots)
+                    })
+                    .collect(),
+            }
+        }
+
+        fn get_snapshot_name(snapshot: u64) -> String {
+            let (date, hour) = Ymd::from_unix_ts(snapshot);
+            format!("{} {:02}:00", date, hour)
+        }
+
+        pub fn get_report(&self) -> SnapshotReport {
+            let mut all_peerings = HashSet::<&PeeringKey>::new();
+            let mut snapshot_peerings = HashMap::<u64, (usize, HashSet<&PeeringKey>)>::new();
+            let mut intermittent = BTreeMap::<String, BTreeMap<String, Vec<String>>>::new();
+
+            for (collector, snapshots) in &self.evidence {
+                let mut collector_peerings = HashSet::<&PeeringKey>::new();
+                for (snapshot, peerings) in snapshots {
+                    let (collectors, union) = snapshot_peerings.entry(*snapshot).or_default();
+                    *collectors += 1;
+                    union.extend(peerings.iter());
+                    collector_peerings.extend(peerings.iter());
+                }
+                all_peerings.extend(collector_peerings.iter());
+
+                for peering in collector_peerings {
+                    let mut seen_in: Vec<u64> = snapshots
+                        .iter()
+                        .filter(|(_, peerings)| peerings.contains(peering))
+                        .map(|(snapshot, _)| *snapshot)
+                        .collect();
+                    if seen_in.len() == snapshots.len() {
+                        continue;
+                    }
+                    seen_in.sort();
+                    intermittent.entry(peering.to_string()).or_default().insert(
+                        collector.clone(),
+                        seen_in.into_iter().map(Self::get_snapshot_name).collect(),
+                    );
+                }
+            }
+
+            SnapshotReport {
+                total_peerings: all_peerings.len(),
+                intermittent_peerings: intermittent.len(),
+                snapshots: snapshot_peerings
+                    .into_iter()
+                    .map(|(snapshot, (collectors, peerings))| {
+                        (
+                            Self::get_snapshot_name(snapshot),
+                            SnapshotSummary {
+                                collectors,
+                                peerings: peerings.len(),
+                            },
+                        )
+                    })
+                    .collect(),
+                intermittent,
+            }
+        }
+
+        pub fn to_file(&self, filename: &String, level: LocationLevel) {
+            ensure_dir(filename);
+            let writer = BufWriter::new(File::create(filename).unwrap());
+            serde_json::to_writer_pretty(writer, &self.roll_up(level).get_report()).unwrap();
+            info!("Wrote JSON to {}", filename);
+        }
+    }
+}
@@ -43,6 +43,13 @@ pub mod triple_t1_paths {
             self.triple_t1_paths.contains_key(triple_t1_path)
         }
 
+        /// Merge paths found by another thread into this one, keeping existing routes
+        pub fn merge(&mut self, other: TripleT1Paths) {
+            for (triple_t1_path, route) in other.triple_t1_paths {
+                self.triple_t1_paths.entry(triple_t1_path).or_insert(route);
+            }
+        }
+
         pub fn to_file(&self, filename: &String) {
             ensure_dir(filename);
             let writer = BufWriter::new(File::create(filename).unwrap());
@@ -0,0 +1,183 @@
+pub mod unmapped_communities {
+    use crate::file::ensure_dir;
+    use crate::mrt_asn::asn::MrtAsn;
+    use ipnet::IpNet;
+    use itertools::Itertools;
+    use log::info;
+    use serde::Serialize;
+    use std::collections::{BTreeMap, HashMap};
+    use std::fs::File;
+    use std::io::BufWriter;
+
+    // Number of example prefixes kept for each community
+    const MAX_EXAMPLES: usize = 5;
+    // Number of following ASNs reported for each community
+    const MAX_NEXT_ASNS: usize = 10;
+
+    /// Routes carrying a single unmapped community
+    #[derive(Debug, Default)]
+    pub struct UnmappedCommunity {
+        routes: u64,
+        // The ASN after the tagging ASN in the path, None if it originated the route
+        next_asns: HashMap<Option<MrtAsn>, u64>,
+        examples: Vec<String>,
+    }
+
+    impl UnmappedCommunity {
+        pub fn get_routes(&self) -> u64 {
+            self.routes
+        }
+
+        /// The number of routes seen with each ASN following the tagging ASN
+        pub fn get_next_asns(&self) -> &HashMap<Option<MrtAsn>, u64> {
+            &self.next_asns
+        }
+
+        fn add(&mut self, next_asn: Option<&MrtAsn>, prefix: &IpNet) {
+            self.routes += 1;
+            *self.next_asns.entry(next_asn.cloned()).or_insert(0) += 1;
+            if self.examples.len() < MAX_EXAMPLES {
+                self.examples.push(prefix.to_string());
+            }
+        }
+
+        fn merge(&mut self, other: UnmappedCommunity) {
+            self.routes += other.routes;
+            for (next_asn, count) in other.next_asns {
+                *self.next_asns.entry(next_asn).or_insert(0) += count;
+            }
+            let space = MAX_EXAMPLES.saturating_sub(self.examples.len());
+            self.examples.extend(other.examples.into_iter().take(space));
+        }
+    }
+
+    /// The unmapped communities of a single T1 ASN
+    #[derive(Debug, Default)]
+    struct AsnUnmapped {
+        // Routes through this ASN which its mappings couldn't classify
+        unclassified_routes: u64,
+        communities: HashMap<u16, UnmappedCommunity>,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct CommunityReport {
+        community: String,
+        routes: u64,
+        // Share of this ASN's unclassified routes which carry the community
+        share: f64,
+        // The most common ASNs following the tagging ASN, most common first
+        next_asns: Vec<(String, u64)>,
+        examples: Vec<String>,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct AsnReport {
+        unclassified_routes: u64,
+        communities: Vec<CommunityReport>,
+    }
+
+    /// Communities with a T1's own ASN, which aren't in its mappings, seen on routes
+    /// through that T1 which couldn't be classified. These are candidates for new
+    /// informational communities to document and add to the mappings.
+    #[derive(Debug, Default)]
+    pub struct UnmappedCommunities {
+        asns: HashMap<MrtAsn, AsnUnmapped>,
+    }
+
+    impl UnmappedCommunities {
+        /// Record a route through the ASN which couldn't be classified, and its unmapped communities
+        pub fn add_route(
+            &mut self,
+            asn: &MrtAsn,
+            values: impl Iterator<Item = u16>,
+            next_asn: Option<&MrtAsn>,
+            prefix: &IpNet,
+        ) {
+            let asn_unmapped = self.asns.entry(asn.clone()).or_default();
+            asn_unmapped.unclassified_routes += 1;
+            for value in values {
+                asn_unmapped
+                    .communities
+                    .entry(value)
+                    .or_default()
+                    .add(next_asn, prefix);
+            }
+        }
+
+        /// Merge the communities seen in another file into this one
+        pub fn merge(&mut self, other: UnmappedCommunities) {
+            for (asn, other_unmapped) in other.asns {
+                let asn_unmapped = self.asns.entry(asn).or_default();
+                asn_unmapped.unclassified_routes += other_unmapped.unclassified_routes;
+                for (value, community) in other_unmapped.communities {
+                    asn_unmapped
+                        .communities
+                        .entry(value)
+                        .or_default()
+                        .merge(community);
+                }
+            }
+        }
+
+        /// Each T1 ASN, community value and the routes seen with it
+        pub fn iter(&self) -> impl Iterator<Item = (&MrtAsn, u16, &UnmappedCommunity)> {
+            self.asns.iter().flat_map(|(asn, asn_unmapped)| {
+                asn_unmapped
+                    .communities
+                    .iter()
+                    .map(move |(value, community)| (asn, *value, community))
+            })
+        }
+
+        /// Per ASN, the unmapped communities ranked by how many routes carry them
+        fn get_report(&self) -> BTreeMap<String, AsnReport> {
+            self.asns
+                .iter()
+                .map(|(asn, asn_unmapped)| {
+                    let communities = asn_unmapped
+                        .communities
+                        .iter()
+                        .sorted_by_key(|(value, community)| {
+                            (std::cmp::Reverse(community.routes), **value)
+                        })
+                        .map(|(value, community)| CommunityReport {
+                            community: format!("{}:{}", asn, value),
+                            routes: community.routes,
+                            share: community.routes as f64
+                                / asn_unmapped.unclassified_routes as f64,
+                            next_asns: community
+                                .next_asns
+                                .iter()
+                                .map(|(next_asn, count)| {
+                                    let next_asn = next_asn
+                                        .as_ref()
+                                        .map_or(String::from("origin"), |asn| format!("AS{}", asn));
+                                    (next_asn, *count)
+                                })
+                                .sorted_by(|(a, a_count), (b, b_count)| {
+                                    b_count.cmp(a_count).then(a.cmp(b))
+                                })
+                                .take(MAX_NEXT_ASNS)
+                                .collect(),
+                            examples: community.examples.clone(),
+                        })
+                        .collect();
+                    (
+                        format!("AS{}", asn),
+                        AsnReport {
+                            unclassified_routes: asn_unmapped.unclassified_routes,
+                            communities,
+                        },
+                    )
+                })
+                .collect()
+        }
+
+        pub fn to_file(&self, filename: &String) {
+            ensure_dir(filename);
+            let writer = BufWriter::new(File::create(filename).unwrap());
+            serde_json::to_writer_pretty(writer, &self.get_report()).unwrap();
+            info!("Wrote JSON to {}", filename);
+        }
+    }
+}
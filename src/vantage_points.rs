@@ -0,0 +1,291 @@
+pub mod vantage_point_ranking {
+    use crate::args::cli_args::LocationLevel;
+    use crate::coverage::collector_coverage::CollectorPeer;
+    use crate::file::{ensure_dir, get_source_name};
+    use crate::mrt_asn::asn::MrtAsn;
+    use crate::mrt_route::route::{IpVersion, Route};
+    use crate::peer_attrs::peer_data::{PeerLocation, PeerType};
+    use log::info;
+    use serde::Serialize;
+    use std::cmp::Reverse;
+    use std::collections::{BinaryHeap, HashMap, HashSet};
+    use std::fmt;
+    use std::fs::File;
+    use std::hash::Hash;
+    use std::io::BufWriter;
+
+    /// A single classified peering, as it would appear in PeeringData
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    pub struct PeeringKey {
+        local_as: MrtAsn,
+        peer_as: MrtAsn,
+        peer_location: PeerLocation,
+        peer_type: PeerType,
+        ip_version: IpVersion,
+    }
+
+    impl fmt::Display for PeeringKey {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                f,
+                "AS{} AS{} {} {:?} {:?}",
+                self.local_as, self.peer_as, self.peer_location, self.peer_type, self.ip_version
+            )
+        }
+    }
+
+    impl PeeringKey {
+        pub fn from(route: &Route) -> Self {
+            Self {
+                local_as: route.get_local_as().clone(),
+                peer_as: route.get_peer_as().clone(),
+                peer_location: route.get_peer_location().clone(),
+                peer_type: route.get_peer_type().clone(),
+                ip_version: route.get_ip_version(),
+            }
+        }
+
+        pub fn roll_up(&self, level: LocationLevel) -> Self {
+            Self {
+                peer_location: self.peer_location.roll_up(level),
+                ..self.clone()
+            }
+        }
+    }
+
+    /// A BGP peer of a specific route collector
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    pub struct VantagePoint {
+        collector: String,
+        peer: CollectorPeer,
+    }
+
+    impl fmt::Display for VantagePoint {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{} {}", self.collector, self.peer)
+        }
+    }
+
+    /// The value of a vantage point, or a collector, relative to all others
+    #[derive(Debug, Serialize)]
+    pub struct Ranking {
+        name: String,
+        // Classified peerings seen via this vantage point
+        peerings: usize,
+        // Classified peerings which are seen only via this vantage point
+        unique_peerings: usize,
+        // New peerings this vantage point adds, given all higher ranked vantage points
+        marginal_peerings: usize,
+        // Peerings found by this vantage point and all higher ranked vantage points
+        cumulative_peerings: usize,
+    }
+
+    /// Peerings contributed by all the collectors from a single MRT source
+    #[derive(Debug, Default, Serialize)]
+    pub struct SourceSummary {
+        collectors: usize,
+        peerings: usize,
+        unique_peerings: usize,
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct VantagePointReport {
+        total_peerings: usize,
+        collector_peers: Vec<Ranking>,
+        collectors: Vec<Ranking>,
+        sources: HashMap<String, SourceSummary>,
+    }
+
+    /// The classified peerings which each vantage point provides evidence for
+    #[derive(Debug, Default)]
+    pub struct VantagePoints {
+        evidence: HashMap<VantagePoint, HashSet<PeeringKey>>,
+    }
+
+    impl VantagePoints {
+        pub fn add_route(&mut self, collector: &str, route: &Route) {
+            let vantage_point = VantagePoint {
+                collector: collector.to_owned(),
+                peer: CollectorPeer::from(route.get_peer()),
+            };
+            self.evidence
+                .entry(vantage_point)
+                .or_default()
+                .insert(PeeringKey::from(route));
+        }
+
+        /// Merge the evidence seen in another file into this one
+        pub fn merge(&mut self, other: VantagePoints) {
+            for (vantage_point, peerings) in other.evidence {
+                self.evidence
+                    .entry(vantage_point)
+                    .or_default()
+                    .extend(peerings);
+            }
+        }
+
+        /// Merge the peerings which are the same at the given location level
+        pub fn roll_up(&self, level: LocationLevel) -> Self {
+            Self {
+                evidence: self
+                    .evidence
+                    .iter()
+                    .map(|(vantage_point, peerings)| {
+                        (
+                            vantage_point.clone(),
+                            peerings.iter().map(|p| p.roll_up(level)).collect(),
+                        )
+                    })
+                    .collect(),
+            }
+        }
+
+        /// Count how many vantage points see each peering
+        fn count_peerings<K>(
+            evidence: &HashMap<K, HashSet<PeeringKey>>,
+        ) -> HashMap<&PeeringKey, usize> {
+            let mut counts = HashMap::<&PeeringKey, usize>::new();
+            for peerings in evidence.values() {
+                for peering in peerings {
+                    *counts.entry(peering).or_insert(0) += 1;
+                }
+            }
+            counts
+        }
+
+        /// Greedily rank vantage points, picking the one which adds the most new
+        /// peerings at each step. Ties are broken on the total peerings seen, then the name.
+        /// The new peerings of a vantage point can only fall as others are picked, so the
+        /// last count is an upper bound, and only the best candidate needs counting again.
+        fn rank<K: fmt::Display>(evidence: &HashMap<K, HashSet<PeeringKey>>) -> Vec<Ranking> {
+            let counts = Self::count_peerings(evidence);
+            let candidates: Vec<(String, &HashSet<PeeringKey>)> = evidence
+                .iter()
+                .map(|(name, peerings)| (name.to_string(), peerings))
+                .collect();
+            let mut queue: BinaryHeap<(usize, usize, Reverse<&str>, usize)> = candidates
+                .iter()
+                .enumerate()
+                .map(|(i, (name, peerings))| {
+                    (peerings.len(), peerings.len(), Reverse(name.as_str()), i)
+                })
+                .collect();
+            let mut found = HashSet::<&PeeringKey>::new();
+            let mut rankings = Vec::<Ranking>::new();
+
+            while let Some((bound, total, name, i)) = queue.pop() {
+                let peerings = candidates[i].1;
+                let marginal_peerings = peerings.iter().filter(|p| !found.contains(p)).count();
+                if marginal_peerings < bound {
+                    queue.push((marginal_peerings, total, name, i));
+                    continue;
+                }
+
+                found.extend(peerings.iter());
+                rankings.push(Ranking {
+                    name: name.0.to_owned(),
+                    peerings: total,
+                    unique_peerings: peerings.iter().filter(|p| counts[p] == 1).count(),
+                    marginal_peerings,
+                    cumulative_peerings: found.len(),
+                });
+            }
+
+            rankings
+        }
+
+        pub fn get_report(&self) -> VantagePointReport {
+            let mut collector_evidence = HashMap::<String, HashSet<PeeringKey>>::new();
+            for (vantage_point, peerings) in &self.evidence {
+                collector_evidence
+                    .entry(vantage_point.collector.clone())
+                    .or_default()
+                    .extend(peerings.iter().cloned());
+            }
+
+            let mut source_evidence = HashMap::<String, HashSet<PeeringKey>>::new();
+            let mut sources = HashMap::<String, SourceSummary>::new();
+            for (collector, peerings) in &collector_evidence {
+                let source = get_source_name(collector);
+                sources.entry(source.clone()).or_default().collectors += 1;
+                source_evidence
+                    .entry(source)
+                    .or_default()
+                    .extend(peerings.iter().cloned());
+            }
+            let source_counts = Self::count_peerings(&source_evidence);
+            for (source, peerings) in &source_evidence {
+                let summary = sources.get_mut(source).unwrap();
+                summary.peerings = peerings.len();
+                summary.unique_peerings = peerings.iter().filter(|p| source_counts[p] == 1).count();
+            }
+
+            VantagePointReport {
+                total_peerings: Self::count_peerings(&self.evidence).len(),
+                collector_peers: Self::rank(&self.evidence),
+                collectors: Self::rank(&collector_evidence),
+                sources,
+            }
+        }
+
+        pub fn to_file(&self, filename: &String, level: LocationLevel) {
+            ensure_dir(filename);
+            let writer = BufWriter::new(File::create(filename).unwrap());
+            serde_json::to_writer_pretty(writer, &self.roll_up(level).get_report()).unwrap();
+            info!("Wrote JSON to {}", filename);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn peerings(peer_asns: &[u32]) -> HashSet<PeeringKey> {
+            peer_asns
+                .iter()
+                .map(|peer_as| PeeringKey {
+                    local_as: MrtAsn::from_u32(174),
+                    peer_as: MrtAsn::from_u32(*peer_as),
+                    peer_location: PeerLocation::NONE_FOUND,
+                    peer_type: PeerType::Peer,
+                    ip_version: IpVersion::Ipv4,
+                })
+                .collect()
+        }
+
+        #[test]
+        fn ranks_by_new_peerings_given_those_ranked_above() {
+            let evidence = HashMap::from([
+                ("a", peerings(&[1, 2, 3, 4])),
+                ("b", peerings(&[1, 2, 3])),
+                ("c", peerings(&[5, 6])),
+                ("d", peerings(&[4, 5])),
+                ("e", peerings(&[1])),
+            ]);
+            let rankings = VantagePoints::rank(&evidence);
+
+            let ranked: Vec<(&str, usize, usize, usize)> = rankings
+                .iter()
+                .map(|r| {
+                    (
+                        r.name.as_str(),
+                        r.marginal_peerings,
+                        r.cumulative_peerings,
+                        r.unique_peerings,
+                    )
+                })
+                .collect();
+            // b and e add nothing once a is ranked, and are ordered by their total peerings
+            assert_eq!(
+                ranked,
+                [
+                    ("a", 4, 4, 0),
+                    ("c", 2, 6, 1),
+                    ("b", 0, 6, 0),
+                    ("d", 0, 6, 0),
+                    ("e", 0, 6, 0)
+                ]
+            );
+        }
+    }
+}